@@ -0,0 +1,117 @@
+use std::{collections::HashMap, error::Error, fmt::Display, fs, path::Path, str::FromStr};
+
+/// Settings read from a file of `key = value` lines, where `#` starts a comment
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Every setting under `prefix.`, keyed by the rest of its name
+    pub fn section(&self, prefix: &str) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().filter_map(move |(key, value)| {
+            key.strip_prefix(prefix)
+                .and_then(|key| key.strip_prefix('.'))
+                .map(|key| (key, value.as_str()))
+        })
+    }
+
+    /// The setting parsed as a `T`, or `default` when it isn't set
+    pub fn get_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, ConfigError> {
+        self.get(key).map_or(Ok(default), |value| {
+            value
+                .parse()
+                .map_err(|_| ConfigError::InvalidValue(key.into(), value.into()))
+        })
+    }
+}
+
+impl FromStr for Config {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = HashMap::new();
+        for (line_number, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(ConfigError::InvalidLine(line_number + 1))?;
+            values.insert(key.trim().into(), value.trim().into());
+        }
+        Ok(Config { values })
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    IO(std::io::Error),
+    InvalidLine(usize),
+    InvalidValue(String, String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ConfigError::IO(ref e) => e.fmt(f),
+            ConfigError::InvalidLine(line) => {
+                write!(f, "line {line} is not of the form `key = value`")
+            }
+            ConfigError::InvalidValue(ref key, ref value) => {
+                write!(f, "\"{value}\" is not a valid value for {key}")
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(value: std::io::Error) -> Self {
+        ConfigError::IO(value)
+    }
+}
+
+impl Error for ConfigError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config = Config::from_str(
+            "# weights\nnext.weight.due = 10\n\nnext.tag.urgent=5 # very\nnext.tag.later = -2\n",
+        )
+        .unwrap();
+        assert_eq!(config.get("next.weight.due"), Some("10"));
+        assert_eq!(config.get_or("next.weight.age", 2.0).unwrap(), 2.0);
+
+        let mut tags: Vec<(&str, &str)> = config.section("next.tag").collect();
+        tags.sort();
+        assert_eq!(tags, [("later", "-2"), ("urgent", "5")]);
+    }
+
+    #[test]
+    fn parse_config_bad_line() {
+        let config = Config::from_str("user = me\nnext.weight.due\n");
+        assert!(matches!(config, Err(ConfigError::InvalidLine(2))));
+    }
+
+    #[test]
+    fn parse_config_bad_number() {
+        let config = Config::from_str("next.weight.due = soon").unwrap();
+        assert!(matches!(
+            config.get_or("next.weight.due", 12.0),
+            Err(ConfigError::InvalidValue(_, _))
+        ));
+    }
+}
@@ -0,0 +1,749 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value, json};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::{error::Error, fmt::Display};
+
+use crate::task_tracker::{
+    ListFilter, TaskTracker,
+    plain_text_task_tracker::PlainTextTaskTrackerError,
+    storage::{Conflict, Storage, TaskFile},
+};
+
+type Tracker = dyn TaskTracker<Err = PlainTextTaskTrackerError>;
+
+/// One line sent by a client, with where its response goes
+type Request = (String, Sender<Option<Value>>);
+
+/// The socket of the daemon owning the task file at `file_path`, e.g. `tasks.txt.sock`
+pub fn socket_path(file_path: &Path) -> PathBuf {
+    let mut socket = file_path.as_os_str().to_owned();
+    socket.push(".sock");
+    PathBuf::from(socket)
+}
+
+/// Listens on `socket`, replacing the socket of a daemon that is no longer running
+pub fn listen(socket: &Path) -> Result<UnixListener, DaemonError> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(DaemonError::AlreadyRunning(socket.to_path_buf()));
+        }
+        std::fs::remove_file(socket)?;
+    }
+    Ok(UnixListener::bind(socket)?)
+}
+
+/// Serves clients until the daemon is stopped. Each client is listened to on a thread of its
+/// own, so one that stays connected never holds up the others, while their requests are carried
+/// out one at a time in the order they arrive
+pub fn serve(listener: &UnixListener, tracker: &Tracker) {
+    let (sender, requests) = mpsc::channel::<Request>();
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            for stream in listener.incoming() {
+                let sender = sender.clone();
+                match stream {
+                    Ok(stream) => {
+                        std::thread::spawn(move || {
+                            if let Err(e) = session(stream, &sender) {
+                                eprintln!("Error serving a client: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => eprintln!("Error accepting a client: {e}"),
+                }
+            }
+        });
+        for (line, response) in requests {
+            // A client that disconnected before its answer needs none
+            let _ = response.send(respond(&line, tracker));
+        }
+    });
+}
+
+fn session(stream: UnixStream, requests: &Sender<Request>) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (sender, response) = mpsc::channel();
+        if requests.send((line, sender)).is_err() {
+            return Ok(());
+        }
+        if let Ok(Some(response)) = response.recv() {
+            writeln!(writer, "{response}")?;
+        }
+    }
+    Ok(())
+}
+
+/// The JSON-RPC 2.0 response to one request, none for a notification
+fn respond(line: &str, tracker: &Tracker) -> Option<Value> {
+    let (id, result) = match serde_json::from_str::<Value>(line) {
+        Ok(request) => {
+            let id = request.get("id").cloned();
+            let result = match request.get("method").and_then(Value::as_str) {
+                Some(method) => call(
+                    method,
+                    request.get("params").cloned().unwrap_or(Value::Null),
+                    tracker,
+                ),
+                None => Err(RpcError::InvalidRequest),
+            };
+            (id?, result)
+        }
+        Err(e) => (Value::Null, Err(RpcError::Parse(e.to_string()))),
+    };
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => {
+            let mut error = json!({ "code": e.code(), "message": e.to_string() });
+            if let RpcError::Conflict(file) = e {
+                error["data"] = json!({ "file": file });
+            }
+            json!({ "jsonrpc": "2.0", "id": id, "error": error })
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct FileParams {
+    file: TaskFile,
+}
+
+#[derive(Deserialize)]
+struct ChangeParams {
+    file: TaskFile,
+    #[serde(default)]
+    contents: String,
+    /// What the client last read of the file, null when it didn't exist. Left out, the change
+    /// is made whatever the file holds
+    #[serde(default, deserialize_with = "present")]
+    expected: Option<Option<String>>,
+}
+
+/// Tells an `expected` of null apart from one left out
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<String>>, D::Error> {
+    Option::<String>::deserialize(deserializer).map(Some)
+}
+
+/// A file and what it holds, `None` when it doesn't exist
+#[derive(Deserialize)]
+struct FileContents {
+    file: TaskFile,
+    contents: Option<String>,
+}
+
+/// Every change one command made, along with what it read of each file before making them
+#[derive(Deserialize)]
+struct CommitParams {
+    #[serde(default)]
+    expected: Vec<FileContents>,
+    changes: Vec<FileContents>,
+}
+
+/// Fails when `file` no longer holds what the client read, as the change would then undo
+/// whatever another client wrote in between
+fn check_contents(
+    storage: &dyn Storage,
+    file: TaskFile,
+    expected: Option<&str>,
+) -> Result<(), RpcError> {
+    let current = match storage.exists(file)? {
+        true => Some(storage.read(file)?),
+        false => None,
+    };
+    match current.as_deref() == expected.map(str::as_bytes) {
+        true => Ok(()),
+        false => Err(RpcError::Conflict(file)),
+    }
+}
+
+fn check_expected(storage: &dyn Storage, params: &ChangeParams) -> Result<(), RpcError> {
+    match params.expected {
+        Some(ref expected) => check_contents(storage, params.file, expected.as_deref()),
+        None => Ok(()),
+    }
+}
+
+fn call(method: &str, params: Value, tracker: &Tracker) -> Result<Value, RpcError> {
+    let storage = tracker.storage();
+    match method {
+        "exists" => {
+            let params: FileParams = parse_params(params)?;
+            Ok(json!(storage.exists(params.file)?))
+        }
+        "read" => {
+            let params: FileParams = parse_params(params)?;
+            String::from_utf8(storage.read(params.file)?)
+                .map(Value::String)
+                .map_err(|e| RpcError::Failed(e.to_string()))
+        }
+        "write" => {
+            let params: ChangeParams = parse_params(params)?;
+            check_expected(storage, &params)?;
+            storage.write(params.file, params.contents.as_bytes())?;
+            Ok(Value::Null)
+        }
+        "append" => {
+            let params: ChangeParams = parse_params(params)?;
+            check_expected(storage, &params)?;
+            storage.append(params.file, params.contents.as_bytes())?;
+            Ok(Value::Null)
+        }
+        "remove" => {
+            let params: ChangeParams = parse_params(params)?;
+            check_expected(storage, &params)?;
+            storage.remove(params.file)?;
+            Ok(Value::Null)
+        }
+        // Requests are carried out one at a time, so nothing changes between the checks and the
+        // changes
+        "commit" => {
+            let params: CommitParams = parse_params(params)?;
+            for expected in &params.expected {
+                check_contents(storage, expected.file, expected.contents.as_deref())?;
+            }
+            for change in params.changes {
+                match change.contents {
+                    Some(contents) => storage.write(change.file, contents.as_bytes())?,
+                    None => storage.remove(change.file)?,
+                }
+            }
+            Ok(Value::Null)
+        }
+        "list" => {
+            let filter: ListFilter = match params {
+                Value::Null => ListFilter::default(),
+                params => parse_params(params)?,
+            };
+            if !tracker.exists()? {
+                return Ok(json!([]));
+            }
+            serde_json::to_value(tracker.listed_tasks(filter)?)
+                .map_err(|e| RpcError::Failed(e.to_string()))
+        }
+        _ => Err(RpcError::MethodNotFound(method.into())),
+    }
+}
+
+fn parse_params<T: for<'a> Deserialize<'a>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::InvalidParams(e.to_string()))
+}
+
+/// The task file and its archive as the daemon owning them serves them. The changes of a
+/// command are held back until it commits them all at once, which the daemon refuses if another
+/// client changed what the command read in the meantime
+pub struct DaemonStorage {
+    connection: RefCell<Connection>,
+    next_id: Cell<u64>,
+    /// What each file held when the command first read it, `None` when it didn't exist
+    read: RefCell<HashMap<TaskFile, Option<String>>>,
+    /// Each file the command changed, as it left it
+    changed: RefCell<HashMap<TaskFile, Option<String>>>,
+}
+
+struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Connection {
+    fn open(socket: &Path) -> std::io::Result<Self> {
+        let writer = UnixStream::connect(socket)?;
+        Ok(Connection {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    fn call(&mut self, request: &Value) -> std::io::Result<Value> {
+        writeln!(self.writer, "{request}")?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        let mut response: Value = serde_json::from_str(&line)?;
+        let Some(error) = response.get("error") else {
+            return Ok(response["result"].take());
+        };
+        let conflict = error.get("data").and_then(|data| data.get("file"));
+        match conflict.and_then(|file| serde_json::from_value(file.clone()).ok()) {
+            Some(file) => Err(std::io::Error::other(Conflict(file))),
+            None => Err(std::io::Error::other(
+                error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("the daemon failed")
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+impl DaemonStorage {
+    /// Connects to the daemon listening on `socket`, or gives `None` when there is no socket
+    pub fn connect(socket: &Path) -> std::io::Result<Option<Self>> {
+        if !socket.exists() {
+            return Ok(None);
+        }
+        Ok(Some(DaemonStorage {
+            connection: RefCell::new(Connection::open(socket)?),
+            next_id: Cell::new(1),
+            read: RefCell::new(HashMap::new()),
+            changed: RefCell::new(HashMap::new()),
+        }))
+    }
+
+    /// Sends one request, failing rather than connecting again when the daemon is gone, since
+    /// the file may have changed while this client wasn't connected
+    fn call(&self, method: &str, params: Value) -> std::io::Result<Value> {
+        let id = self.next_id.replace(self.next_id.get() + 1);
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        self.connection
+            .borrow_mut()
+            .call(&request)
+            .map_err(|e| match e.kind() {
+                ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::UnexpectedEof => {
+                    std::io::Error::new(e.kind(), "the daemon closed the connection")
+                }
+                _ => e,
+            })
+    }
+
+    /// What `file` holds for the command, with its own changes
+    fn contents(&self, file: TaskFile) -> std::io::Result<Option<String>> {
+        if let Some(contents) = self.changed.borrow().get(&file) {
+            return Ok(contents.clone());
+        }
+        if let Some(contents) = self.read.borrow().get(&file) {
+            return Ok(contents.clone());
+        }
+        let exists = self
+            .call("exists", json!({ "file": file }))?
+            .as_bool()
+            .ok_or_else(|| std::io::Error::other("the daemon answered exists with no boolean"))?;
+        let contents = match exists {
+            true => match self.call("read", json!({ "file": file }))? {
+                Value::String(contents) => Some(contents),
+                _ => {
+                    return Err(std::io::Error::other(
+                        "the daemon answered read with no text",
+                    ));
+                }
+            },
+            false => None,
+        };
+        self.read.borrow_mut().insert(file, contents.clone());
+        Ok(contents)
+    }
+}
+
+impl Storage for DaemonStorage {
+    fn exists(&self, file: TaskFile) -> std::io::Result<bool> {
+        Ok(self.contents(file)?.is_some())
+    }
+
+    fn read(&self, file: TaskFile) -> std::io::Result<Vec<u8>> {
+        self.contents(file)?
+            .map(String::into_bytes)
+            .ok_or_else(|| ErrorKind::NotFound.into())
+    }
+
+    fn write(&self, file: TaskFile, contents: &[u8]) -> std::io::Result<()> {
+        let contents = std::str::from_utf8(contents).map_err(std::io::Error::other)?;
+        self.changed
+            .borrow_mut()
+            .insert(file, Some(contents.into()));
+        Ok(())
+    }
+
+    fn append(&self, file: TaskFile, contents: &[u8]) -> std::io::Result<()> {
+        let contents = std::str::from_utf8(contents).map_err(std::io::Error::other)?;
+        let mut appended = self.contents(file)?.unwrap_or_default();
+        appended.push_str(contents);
+        self.changed.borrow_mut().insert(file, Some(appended));
+        Ok(())
+    }
+
+    fn remove(&self, file: TaskFile) -> std::io::Result<()> {
+        self.contents(file)?;
+        self.changed.borrow_mut().insert(file, None);
+        Ok(())
+    }
+
+    fn commit(&self) -> std::io::Result<()> {
+        let read = self.read.take();
+        let changed = self.changed.take();
+        if changed.is_empty() {
+            return Ok(());
+        }
+        let list = |files: HashMap<TaskFile, Option<String>>| -> Vec<Value> {
+            files
+                .into_iter()
+                .map(|(file, contents)| json!({ "file": file, "contents": contents }))
+                .collect()
+        };
+        let params = json!({ "expected": list(read), "changes": list(changed) });
+        self.call("commit", params).map(drop)
+    }
+
+    fn rollback(&self) {
+        self.read.take();
+        self.changed.take();
+    }
+}
+
+/// A request the daemon could not carry out, with its JSON-RPC error code
+#[derive(Debug)]
+enum RpcError {
+    Parse(String),
+    InvalidRequest,
+    MethodNotFound(String),
+    InvalidParams(String),
+    /// The file changed since the client read it
+    Conflict(TaskFile),
+    Failed(String),
+}
+
+impl RpcError {
+    fn code(&self) -> i64 {
+        match *self {
+            RpcError::Parse(_) => -32700,
+            RpcError::InvalidRequest => -32600,
+            RpcError::MethodNotFound(_) => -32601,
+            RpcError::InvalidParams(_) => -32602,
+            RpcError::Conflict(_) => -32001,
+            RpcError::Failed(_) => -32000,
+        }
+    }
+}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            RpcError::Parse(ref reason) => write!(f, "invalid JSON: {reason}"),
+            RpcError::InvalidRequest => "a request needs a method".fmt(f),
+            RpcError::MethodNotFound(ref method) => write!(f, "no method named \"{method}\""),
+            RpcError::InvalidParams(ref reason) => write!(f, "invalid params: {reason}"),
+            RpcError::Conflict(file) => Conflict(file).fmt(f),
+            RpcError::Failed(ref reason) => reason.fmt(f),
+        }
+    }
+}
+
+impl From<std::io::Error> for RpcError {
+    fn from(value: std::io::Error) -> Self {
+        RpcError::Failed(value.to_string())
+    }
+}
+
+impl From<PlainTextTaskTrackerError> for RpcError {
+    fn from(value: PlainTextTaskTrackerError) -> Self {
+        RpcError::Failed(value.to_string())
+    }
+}
+
+#[derive(Debug)]
+pub enum DaemonError {
+    IO(std::io::Error),
+    AlreadyRunning(PathBuf),
+}
+
+impl Display for DaemonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            DaemonError::IO(ref e) => e.fmt(f),
+            DaemonError::AlreadyRunning(ref socket) => {
+                write!(f, "a daemon is already listening on {}", socket.display())
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for DaemonError {
+    fn from(value: std::io::Error) -> Self {
+        DaemonError::IO(value)
+    }
+}
+
+impl Error for DaemonError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::NewTask;
+    use crate::task_tracker::plain_text_task_tracker::{PipeTable, PlainTextTaskTracker};
+    use chrono::DateTime;
+    use std::time::Duration;
+
+    /// Runs a daemon for a new task file, returning the file and the daemon's socket
+    fn start(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("on_job_daemon_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("tasks.txt");
+        let socket = socket_path(&file_path);
+        let listener = listen(&socket).unwrap();
+        let served = file_path.clone();
+        std::thread::spawn(move || {
+            serve(&listener, &PlainTextTaskTracker::<PipeTable>::new(served));
+        });
+        (file_path, socket)
+    }
+
+    fn client(file_path: &Path, socket: &Path) -> PlainTextTaskTracker {
+        let storage = DaemonStorage::connect(socket).unwrap().unwrap();
+        PlainTextTaskTracker::new(file_path).with_storage(Box::new(storage))
+    }
+
+    fn new_task(name: &str) -> NewTask {
+        NewTask {
+            name: name.into(),
+            tags: None,
+            deadline: DateTime::parse_from_rfc3339("2025-03-10T16:00:00Z")
+                .unwrap()
+                .to_utc(),
+            parent: None,
+            depends_on: None,
+            priority: None,
+            estimate: None,
+            scheduled: None,
+            wait: None,
+            assignee: None,
+            fields: Vec::new(),
+            links: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    fn rpc(socket: &Path, requests: &[&str]) -> Vec<Value> {
+        let mut connection = Connection::open(socket).unwrap();
+        for request in requests {
+            writeln!(connection.writer, "{request}").unwrap();
+        }
+        connection
+            .writer
+            .shutdown(std::net::Shutdown::Write)
+            .unwrap();
+        connection
+            .reader
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn daemon_owns_the_task_file() {
+        let (file_path, socket) = start("owns");
+        assert_eq!(socket, file_path.with_file_name("tasks.txt.sock"));
+        assert!(matches!(
+            listen(&socket),
+            Err(DaemonError::AlreadyRunning(_))
+        ));
+
+        let tracker = client(&file_path, &socket);
+        assert!(!tracker.exists().unwrap());
+        assert_eq!(tracker.add_task(new_task("Call Acme")).unwrap(), 1);
+        assert_eq!(tracker.add_task(new_task("Fix the roof")).unwrap(), 2);
+        let listed = tracker.listed_tasks(ListFilter::default()).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1].task.name, "Fix the roof");
+        assert!(!file_path.exists(), "nothing is written before the commit");
+        tracker.storage().commit().unwrap();
+        drop(tracker);
+
+        let written = std::fs::read_to_string(&file_path).unwrap();
+        assert!(written.starts_with("| Call Acme |"), "{written}");
+        std::fs::remove_dir_all(file_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn daemon_answers_json_rpc() {
+        let (file_path, socket) = start("rpc");
+        let responses = rpc(
+            &socket,
+            &[
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "list"}"#,
+                r#"{"jsonrpc": "2.0", "method": "append", "params": {"file": "tasks", "contents": "| Call Acme | work | todo | 2025-03-10T16:00:00+00:00 | uid=1 |\n"}}"#,
+                r#"{"jsonrpc": "2.0", "id": 2, "method": "list", "params": {"tags": ["work"]}}"#,
+                r#"{"jsonrpc": "2.0", "id": 3, "method": "read", "params": {"file": "backup"}}"#,
+                r#"{"jsonrpc": "2.0", "id": 4, "method": "rename"}"#,
+                "not json",
+            ],
+        );
+        assert_eq!(responses.len(), 5, "notifications get no answer");
+        assert_eq!(
+            responses[0],
+            json!({"jsonrpc": "2.0", "id": 1, "result": []})
+        );
+        assert_eq!(responses[1]["result"][0]["name"], "Call Acme");
+        assert_eq!(responses[1]["result"][0]["id"], 0);
+        assert_eq!(responses[2]["error"]["code"], -32602);
+        assert_eq!(
+            responses[3]["error"],
+            json!({"code": -32601, "message": "no method named \"rename\""})
+        );
+        assert_eq!(responses[4]["id"], Value::Null);
+        assert_eq!(responses[4]["error"]["code"], -32700);
+        std::fs::remove_dir_all(file_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn daemon_serves_clients_side_by_side() {
+        let (file_path, socket) = start("turns");
+        let first = client(&file_path, &socket);
+        first.add_task(new_task("Call Acme")).unwrap();
+        first.storage().commit().unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let (other_path, other_socket) = (file_path.clone(), socket.clone());
+        std::thread::spawn(move || {
+            let second = client(&other_path, &other_socket);
+            let id = second.add_task(new_task("Fix the roof")).unwrap();
+            second.storage().commit().unwrap();
+            sender.send(id).unwrap();
+        });
+        // The second client is served while the first is still connected
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), 2);
+        assert_eq!(first.add_task(new_task("Book flights")).unwrap(), 3);
+        first.storage().commit().unwrap();
+        std::fs::remove_dir_all(file_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn daemon_refuses_commits_on_contents_replaced_since() {
+        let (file_path, socket) = start("interleaved");
+        let tracker = client(&file_path, &socket);
+        tracker.add_task(new_task("Call Acme")).unwrap();
+        tracker.storage().commit().unwrap();
+        let first = DaemonStorage::connect(&socket).unwrap().unwrap();
+        let second = DaemonStorage::connect(&socket).unwrap().unwrap();
+        let line = |name: &str, uid: usize| {
+            format!("| {name} |  | todo | 2025-03-10T16:00:00+00:00 | uid={uid} |\n")
+        };
+
+        let read_first = String::from_utf8(first.read(TaskFile::Tasks).unwrap()).unwrap();
+        let read_second = String::from_utf8(second.read(TaskFile::Tasks).unwrap()).unwrap();
+        let changed_first = read_first + &line("Fix the roof", 2);
+        first
+            .write(TaskFile::Tasks, changed_first.as_bytes())
+            .unwrap();
+        assert_eq!(
+            first.read(TaskFile::Tasks).unwrap(),
+            changed_first.as_bytes()
+        );
+        second
+            .write(
+                TaskFile::Tasks,
+                (read_second + &line("Book flights", 3)).as_bytes(),
+            )
+            .unwrap();
+        second
+            .append(TaskFile::Archive, line("Pay rent", 4).as_bytes())
+            .unwrap();
+        first.commit().unwrap();
+        let error = second.commit().unwrap_err();
+        assert!(Conflict::is(&error));
+        assert_eq!(error.to_string(), "the task file changed since it was read");
+        assert!(
+            !tracker.archive_path().exists(),
+            "a refused commit changes nothing"
+        );
+
+        // Run again on what the first client wrote, the second client's change keeps both
+        let read_again = String::from_utf8(second.read(TaskFile::Tasks).unwrap()).unwrap();
+        second
+            .write(
+                TaskFile::Tasks,
+                (read_again + &line("Book flights", 3)).as_bytes(),
+            )
+            .unwrap();
+        second.commit().unwrap();
+        drop((first, second));
+
+        let names: Vec<String> = tracker
+            .listed_tasks(ListFilter::default())
+            .unwrap()
+            .into_iter()
+            .map(|listed| listed.task.name)
+            .collect();
+        assert_eq!(names, ["Call Acme", "Fix the roof", "Book flights"]);
+
+        let responses = rpc(
+            &socket,
+            &[
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "commit", "params": {"expected": [{"file": "tasks", "contents": null}], "changes": [{"file": "tasks", "contents": ""}]}}"#,
+            ],
+        );
+        assert_eq!(
+            responses[0]["error"],
+            json!({
+                "code": -32001,
+                "message": "the task file changed since it was read",
+                "data": {"file": "tasks"},
+            })
+        );
+        std::fs::remove_dir_all(file_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn daemon_keeps_every_task_added_at_once() {
+        const CLIENTS: usize = 20;
+        let (file_path, socket) = start("parallel");
+        let adding: Vec<_> = (0..CLIENTS)
+            .map(|n| {
+                let (file_path, socket) = (file_path.clone(), socket.clone());
+                std::thread::spawn(move || {
+                    let tracker = client(&file_path, &socket);
+                    // What the CLI does with a command refused for a conflict
+                    loop {
+                        tracker.add_task(new_task(&format!("Task {n}"))).unwrap();
+                        match tracker.storage().commit() {
+                            Ok(()) => break,
+                            Err(e) if Conflict::is(&e) => continue,
+                            Err(e) => panic!("{e}"),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in adding {
+            thread.join().unwrap();
+        }
+
+        let listed = client(&file_path, &socket)
+            .listed_tasks(ListFilter::default())
+            .unwrap();
+        assert_eq!(listed.len(), CLIENTS);
+        let mut uids: Vec<usize> = listed.iter().filter_map(|listed| listed.task.uid).collect();
+        uids.sort();
+        uids.dedup();
+        assert_eq!(uids.len(), CLIENTS);
+        std::fs::remove_dir_all(file_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn daemon_storage_fails_once_the_connection_is_lost() {
+        let dir = std::env::temp_dir().join(format!("on_job_daemon_{}_lost", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("tasks.txt.sock");
+        let listener = listen(&socket).unwrap();
+        let storage = DaemonStorage::connect(&socket).unwrap().unwrap();
+        drop(listener.accept().unwrap());
+
+        let error = storage.exists(TaskFile::Tasks).unwrap_err();
+        assert_eq!(error.to_string(), "the daemon closed the connection");
+        // No second connection was opened to carry on
+        listener.set_nonblocking(true).unwrap();
+        assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
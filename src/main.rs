@@ -1,15 +1,38 @@
+mod config;
+#[cfg(unix)]
+mod daemon;
+mod server;
 mod task_tracker;
 mod utils;
 
-use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
+use chrono::{
+    DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta,
+    TimeZone, Utc, Weekday,
+};
 use clap::Parser;
+use config::{Config, ConfigError};
 use std::{
     env::{self},
+    error::Error,
     fmt::Display,
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
 };
-use task_tracker::TaskTracker;
+use task_tracker::{
+    FileFormat, ListFilter, NewTask, OutputFormat, Snooze, TaskEdit, TaskRef, TaskTracker,
+    document_task_tracker::{JsonTaskTracker, TomlTaskTracker, YamlTaskTracker},
+    exchange::{ExchangeFormat, ExportOptions, ImportOptions},
+    field::FieldDefinitions,
+    plain_text_task_tracker::{
+        PipeTable, PlainTextTaskTracker, PlainTextTaskTrackerError, TaskFileFormat,
+    },
+    report::ReportGrouping,
+    selection::{Condition, IdRange, Selection},
+    storage::{Memory, Storage},
+    task::{self, Estimate, Priority, Status, end_of_day},
+    todo_txt_task_tracker::TodoTxtTaskTracker,
+    urgency::UrgencyWeights,
+};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -23,19 +46,56 @@ struct Args {
     command: Commands,
     #[arg(short, long)]
     file: Option<PathBuf>,
+    #[arg(long)]
+    /// Configuration file, read from ON_JOB_CONFIG when not given
+    config: Option<PathBuf>,
+    #[arg(long)]
+    /// Layout of the task file: table, todo.txt, json, yaml or toml. Taken from the file's
+    /// extension when not given, and otherwise from ON_JOB_FILE_FORMAT
+    file_format: Option<FileFormat>,
 }
 
 #[derive(clap::Args, Debug)]
 struct AddTaskArgs {
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = storable_text)]
     /// Name of project
     name: String,
-    #[arg(short, long, num_args=1..)]
+    #[arg(short, long, num_args=1.., value_parser = storable_text)]
     /// Tag(s) to categorized a task
     tags: Option<Vec<String>>,
     #[clap(short, long, default_value_t = DateTimeWrapper::default())]
     /// Deadline by which this task should be complete
     deadline: DateTimeWrapper,
+    #[arg(short, long)]
+    /// Id of the task this is a subtask of
+    parent: Option<usize>,
+    #[arg(long, num_args=1..)]
+    /// Id(s) of tasks that must be complete before this one can start
+    depends_on: Option<Vec<usize>>,
+    #[arg(long)]
+    /// How important the task is: low, medium or high
+    priority: Option<Priority>,
+    #[arg(short, long)]
+    /// Expected effort, as a length of time ("2h", "1h30m") or in points ("3pts")
+    estimate: Option<Estimate>,
+    #[arg(short, long)]
+    /// When work on this task is planned to begin
+    scheduled: Option<DateTimeWrapper>,
+    #[arg(short, long)]
+    /// Hide this task from list until then
+    wait: Option<DateTimeWrapper>,
+    #[arg(long, value_parser = storable_text)]
+    /// Who the task belongs to
+    assign: Option<String>,
+    #[arg(long = "set", value_name = "KEY=VALUE", num_args=1..)]
+    /// Custom field value(s), for fields declared in the config file
+    fields: Vec<String>,
+    #[arg(short, long, num_args=1..)]
+    /// URL(s) or file path(s) the task refers to
+    link: Vec<LinkWrapper>,
+    #[arg(long, value_parser = storable_text)]
+    /// A note to keep with the task, can be given several times
+    note: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -45,7 +105,7 @@ impl Default for DateTimeWrapper {
     fn default() -> Self {
         Self(
             Local::now()
-                .with_time(NaiveTime::from_hms_opt(17, 0, 0).expect("Hardcoded values"))
+                .with_time(end_of_day())
                 .single()
                 .expect("Hardcoded values"),
         )
@@ -71,9 +131,8 @@ impl FromStr for DateTimeWrapper {
                 Some(datetime) => Ok(DateTimeWrapper(datetime.with_timezone(&Local))),
                 None => Err("CannotBeParsedToTimezone".into()),
             }
-        } else if let Ok(Some(naive_datetime)) =
-            NaiveDate::parse_from_str(s, DATE_FORMAT).map(|date| date.and_hms_opt(17, 0, 0))
-        {
+        } else if let Ok(date) = NaiveDate::parse_from_str(s, DATE_FORMAT) {
+            let naive_datetime = date.and_time(end_of_day());
             match local_offset.from_local_datetime(&naive_datetime).single() {
                 Some(datetime) => Ok(DateTimeWrapper(datetime.with_timezone(&Local))),
                 None => Err("CannotBeParsedToDate".into()),
@@ -89,21 +148,328 @@ impl FromStr for DateTimeWrapper {
     }
 }
 
+/// A calendar day, given as a date, "today", "yesterday", "tomorrow" or the most recent weekday
+#[derive(Clone, Debug)]
+struct DateWrapper(NaiveDate);
+
+impl DateWrapper {
+    fn start(&self) -> DateTime<Utc> {
+        Local
+            .from_local_datetime(&self.0.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|start| start.to_utc())
+            .unwrap_or_else(|| self.0.and_time(NaiveTime::MIN).and_utc())
+    }
+
+    fn end(&self) -> DateTime<Utc> {
+        self.0
+            .succ_opt()
+            .map(|next_day| DateWrapper(next_day).start())
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+impl FromStr for DateWrapper {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let today = Local::now().date_naive();
+        let date = match s.trim().to_lowercase().as_str() {
+            "today" => Some(today),
+            "yesterday" => today.pred_opt(),
+            "tomorrow" => today.succ_opt(),
+            day => match day.parse::<Weekday>() {
+                Ok(weekday) => {
+                    let days_back = (7 + today.weekday().num_days_from_monday()
+                        - weekday.num_days_from_monday())
+                        % 7;
+                    today.checked_sub_days(Days::new(days_back.into()))
+                }
+                Err(_) => NaiveDate::parse_from_str(day, "%Y-%m-%d").ok(),
+            },
+        };
+        date.map(DateWrapper)
+            .ok_or_else(|| "Cannot be parsed to a day".into())
+    }
+}
+
+/// A length of time to push a deadline back by, or a new deadline
+#[derive(Clone, Debug)]
+struct SnoozeWrapper(Snooze);
+
+impl FromStr for SnoozeWrapper {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match utils::parse_duration(s.trim().trim_start_matches('+')) {
+            Some(delta) => Ok(SnoozeWrapper(Snooze::By(delta))),
+            None => DateTimeWrapper::from_str(s)
+                .map(|deadline| SnoozeWrapper(Snooze::Until(deadline.0.into())))
+                .map_err(|_| "Cannot be parsed to a duration, Date, Time, or DateTime".into()),
+        }
+    }
+}
+
+/// A length of time like "30d" or "1w2d"
+#[derive(Clone, Copy, Debug)]
+struct DurationWrapper(TimeDelta);
+
+impl FromStr for DurationWrapper {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        utils::parse_duration(s)
+            .map(DurationWrapper)
+            .ok_or_else(|| "Cannot be parsed to a duration like 30d or 1w2d".into())
+    }
+}
+
+/// A CSV header and the column it holds, like "Due Date=deadline"
+#[derive(Clone, Debug)]
+struct MappingWrapper(String, String);
+
+impl FromStr for MappingWrapper {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((header, column)) if !header.trim().is_empty() => {
+                Ok(MappingWrapper(header.trim().into(), column.trim().into()))
+            }
+            _ => Err("Mappings look like HEADER=COLUMN".into()),
+        }
+    }
+}
+
+/// Text given on the command line, refused when it would break the line of its task
+fn storable_text(s: &str) -> Result<String, String> {
+    task::check_text(s).map(|()| s.into())
+}
+
+/// A URL, or a file path made absolute so it still resolves from another directory
+#[derive(Clone, Debug)]
+struct LinkWrapper(String);
+
+impl FromStr for LinkWrapper {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("Links cannot be empty".into());
+        }
+        task::check_text(s)?;
+        if utils::is_url(s) {
+            return Ok(LinkWrapper(s.into()));
+        }
+        std::path::absolute(s)
+            .map(|path| LinkWrapper(path.display().to_string()))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// The tasks a bulk command applies to
+#[derive(clap::Args, Debug)]
+struct SelectionArgs {
+    #[arg(required_unless_present = "conditions", value_name = "ID")]
+    /// id(s) or ranges of ids ("3-7") of tasks, all open tasks when only --where is given
+    ids: Vec<IdRange>,
+    #[arg(long = "where", value_name = "CONDITION")]
+    /// Only tasks matching this condition: tag:<TAG>, status:<STATUS>, priority:<PRIORITY>,
+    /// assignee:<NAME>, field.<NAME>:<VALUE> or overdue; can be given several times
+    conditions: Vec<Condition>,
+    #[arg(short, long)]
+    /// Change several tasks without asking first
+    yes: bool,
+}
+
+impl From<SelectionArgs> for Selection {
+    fn from(args: SelectionArgs) -> Self {
+        Selection {
+            ids: args.ids,
+            conditions: args.conditions,
+        }
+    }
+}
+
 #[derive(clap::Args, Debug)]
 struct CompleteTaskArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+    #[arg(short, long)]
+    /// Complete all subtasks as well, without asking
+    cascade: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct DeleteTaskArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct EditTaskArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+    #[arg(short, long, value_parser = storable_text)]
+    /// New name
+    name: Option<String>,
+    #[arg(short, long)]
+    /// New deadline
+    deadline: Option<DateTimeWrapper>,
+    #[arg(long, num_args=1.., value_parser = storable_text)]
+    /// Tag(s) to add
+    add_tag: Vec<String>,
+    #[arg(long, num_args=1..)]
+    /// Tag(s) to remove
+    remove_tag: Vec<String>,
+    #[arg(long)]
+    /// New priority: low, medium or high
+    priority: Option<Priority>,
+    #[arg(long, value_parser = storable_text)]
+    /// Who the tasks belong to
+    assign: Option<String>,
+    #[arg(long = "set", value_name = "KEY=VALUE", num_args=1..)]
+    /// Custom field value(s), for fields declared in the config file
+    fields: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct SetStatusArgs {
     #[arg(value_parser)]
-    /// id of task to complete
+    /// id of task to update
     id: usize,
+    #[arg(value_parser)]
+    /// New status: todo, in-progress, waiting, cancelled or done
+    status: Status,
+    #[arg(long)]
+    /// Takes the id as the task's uid, which also finds done, cancelled and archived tasks
+    uid: bool,
 }
 
 #[derive(clap::Args, Debug)]
-struct DeleteTaskArgs {
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct CheckArgs {
+    #[command(subcommand)]
+    command: Option<CheckCommands>,
+    #[arg(required = true)]
+    /// id of task whose checklist item to check off
+    id: Option<usize>,
+    #[arg(required = true)]
+    /// Number of the checklist item, as shown by show
+    item: Option<usize>,
+    #[arg(short, long)]
+    /// Uncheck the item instead
+    uncheck: bool,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum CheckCommands {
+    #[command(about = "Adds an item to a task's checklist")]
+    Add(CheckAddArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct CheckAddArgs {
     #[arg(value_parser)]
-    /// id of task to delete
+    /// id of task to add the item to
     id: usize,
+    #[arg(value_parser = storable_text)]
+    /// What needs to be done
+    text: String,
 }
 
 #[derive(clap::Args, Debug)]
+struct ShowTaskArgs {
+    #[arg(value_parser)]
+    /// id of task to show
+    id: usize,
+    #[arg(long)]
+    /// Takes the id as the task's uid, which also finds done, cancelled and archived tasks
+    uid: bool,
+    #[arg(short, long, default_value_t = OutputFormat::Text)]
+    /// Output format: text or json
+    output: OutputFormat,
+}
+
+#[derive(clap::Args, Debug)]
+struct LinkTaskArgs {
+    #[arg(value_parser)]
+    /// id of task to add links to
+    id: usize,
+    #[arg(required = true)]
+    /// URL(s) or file path(s) the task refers to
+    links: Vec<LinkWrapper>,
+}
+
+#[derive(clap::Args, Debug)]
+struct OpenTaskArgs {
+    #[arg(value_parser)]
+    /// id of task whose links to open
+    id: usize,
+    #[arg(short, long)]
+    /// Only print the links instead of opening them
+    print: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct WaitForArgs {
+    #[arg(value_parser)]
+    /// id of task that is waiting
+    id: usize,
+    #[arg(value_parser = storable_text)]
+    /// Who the task is waiting on
+    who: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct SnoozeTaskArgs {
+    #[arg(required = true, value_name = "ID... DURATION|DEADLINE")]
+    /// id(s) or ranges of ids ("3-7") of tasks, all open tasks when only --where is given,
+    /// followed by how long to push the deadline back ("2d", "3h") or the new deadline
+    targets: Vec<String>,
+    #[arg(long = "where", value_name = "CONDITION")]
+    /// Only tasks matching this condition, as for complete
+    conditions: Vec<Condition>,
+    #[arg(short, long)]
+    /// Snooze several tasks without asking first
+    yes: bool,
+}
+
+impl SnoozeTaskArgs {
+    /// Splits the positional arguments into the selected ids and the snooze, which comes last
+    fn split(self) -> Result<(Selection, Snooze), String> {
+        let (until, ids) = self
+            .targets
+            .split_last()
+            .ok_or("missing duration or deadline")?;
+        let until = SnoozeWrapper::from_str(until).map_err(|e| format!("\"{until}\": {e}"))?;
+        let ids = ids
+            .iter()
+            .map(|id| IdRange::from_str(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        if ids.is_empty() && self.conditions.is_empty() {
+            return Err("give the id(s) of the tasks to snooze or a --where condition".into());
+        }
+        let selection = Selection {
+            ids,
+            conditions: self.conditions,
+        };
+        Ok((selection, until.0))
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct DependTaskArgs {
+    #[arg(value_parser)]
+    /// id of task that has to wait
+    id: usize,
+    #[arg(long, required = true, num_args=1..)]
+    /// id(s) of tasks it has to wait on
+    on: Vec<usize>,
+}
+
+#[derive(clap::Args, Debug, Default)]
 struct ListTasksArgs {
     #[arg(short, long)]
     /// Show both complete and incomplete tasks
@@ -114,6 +480,155 @@ struct ListTasksArgs {
     #[arg(short, long, num_args=1..)]
     /// Only show tasks with specific tags
     tags: Option<Vec<String>>,
+    #[arg(short, long)]
+    /// Only show incomplete tasks that are not blocked by a dependency
+    ready: bool,
+    #[arg(short, long)]
+    /// Also show tasks whose wait date has not passed yet
+    deferred: bool,
+    #[arg(short, long)]
+    /// Only show tasks scheduled to begin on this day
+    scheduled: Option<DateWrapper>,
+    #[arg(long)]
+    /// Only show tasks snoozed at least snooze.often times (3 by default)
+    snoozed_often: bool,
+    #[arg(short, long)]
+    /// Only show tasks assigned to the user set in the configuration file
+    mine: bool,
+    #[arg(long)]
+    /// Only show waiting tasks, longest waiting first
+    waiting: bool,
+    #[arg(long = "field", value_name = "KEY=VALUE", num_args=1..)]
+    /// Only show tasks with these custom field values
+    fields: Vec<String>,
+    #[arg(short, long, num_args=1..)]
+    /// Custom fields to show as extra columns
+    columns: Vec<String>,
+    #[arg(long)]
+    /// Show archived tasks instead
+    archived: bool,
+    #[arg(long, value_name = "TEXT")]
+    /// Only show tasks whose name or notes contain this text
+    search: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExportTasksArgs {
+    #[arg(short, long)]
+    /// Format to write tasks in: ics, taskwarrior, csv, markdown or org
+    format: ExchangeFormat,
+    #[arg(long)]
+    /// Also write a calendar event at the deadline of every open task (ics)
+    events: bool,
+    #[arg(long, value_delimiter = ',')]
+    /// Columns to write, e.g. "name,deadline,notes" (csv)
+    columns: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ImportTasksArgs {
+    #[arg(short, long)]
+    /// Format the tasks are written in: ics, taskwarrior, csv, markdown or org
+    format: ExchangeFormat,
+    #[arg(value_parser)]
+    /// File to read the tasks from, or - for standard input
+    file: PathBuf,
+    #[arg(long = "map", value_name = "HEADER=COLUMN")]
+    /// Column a header holds, e.g. "Due Date=deadline", or nothing to ignore it (csv)
+    mapping: Vec<MappingWrapper>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ConvertArgs {
+    #[arg(long, value_name = "FILE")]
+    /// Task file to read
+    from: PathBuf,
+    #[arg(long, value_name = "FILE")]
+    /// Task file to write
+    to: PathBuf,
+    #[arg(long, value_name = "FORMAT")]
+    /// Layout of the file read, when its extension doesn't tell
+    from_format: Option<FileFormat>,
+    #[arg(long, value_name = "FORMAT")]
+    /// Layout of the file written, when its extension doesn't tell
+    to_format: Option<FileFormat>,
+    #[arg(long)]
+    /// Overwrite the file written if it already exists
+    force: bool,
+    #[arg(long)]
+    /// Write the file even when its format can't keep everything, listing what is lost
+    allow_loss: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct ServeArgs {
+    #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1:8080")]
+    /// Address and port to listen on
+    bind: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct ArchiveTasksArgs {
+    #[arg(long, value_name = "DURATION")]
+    /// Only archive tasks closed longer ago than this, e.g. "30d"
+    older_than: Option<DurationWrapper>,
+}
+
+#[derive(clap::Args, Debug)]
+struct NextTaskArgs {
+    #[arg(default_value_t = 3)]
+    /// Number of tasks to recommend
+    count: usize,
+}
+
+#[derive(clap::Args, Debug)]
+struct StartTaskArgs {
+    #[arg(value_parser)]
+    /// id of task to start working on
+    id: usize,
+}
+
+#[derive(clap::Args, Debug)]
+struct TimeReportArgs {
+    #[arg(short, long)]
+    /// Only count time worked since the start of this day
+    since: Option<DateWrapper>,
+    #[arg(short, long, default_value_t = ReportGrouping::Task)]
+    /// Sum up time per task, tag or day
+    by: ReportGrouping,
+}
+
+#[derive(clap::Args, Debug)]
+struct EstimateReportArgs {
+    #[arg(short, long)]
+    /// Only count tasks due on or after this day
+    since: Option<DateWrapper>,
+    #[arg(short, long)]
+    /// Only count tasks due on or before this day
+    until: Option<DateWrapper>,
+}
+
+#[derive(clap::Args, Debug)]
+struct LoadReportArgs {
+    #[arg(long)]
+    /// Hours available for the rest of the week, read from load.available_hours when not given
+    available: Option<f64>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum ReportCommands {
+    #[command(name = "time", about = "Summarizes time tracked with start and stop")]
+    Time(TimeReportArgs),
+    #[command(
+        name = "estimates",
+        about = "Compares estimated and tracked effort per tag"
+    )]
+    Estimates(EstimateReportArgs),
+    #[command(
+        name = "load",
+        about = "Compares remaining estimates of tasks due this week with available hours"
+    )]
+    Load(LoadReportArgs),
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -124,37 +639,660 @@ enum Commands {
     CompleteTask(CompleteTaskArgs),
     #[command(name = "delete", about = "Removes a task")]
     DeleteTask(DeleteTaskArgs),
+    #[command(
+        name = "edit",
+        about = "Changes the name, deadline, tags or fields of tasks"
+    )]
+    EditTask(EditTaskArgs),
+    #[command(name = "status", about = "Moves a task to another status")]
+    SetStatus(SetStatusArgs),
+    #[command(name = "check", about = "Checks off a checklist item of a task")]
+    Check(CheckArgs),
+    #[command(name = "show", about = "Shows every detail of a task")]
+    ShowTask(ShowTaskArgs),
+    #[command(name = "link", about = "Adds URLs or files a task refers to")]
+    LinkTask(LinkTaskArgs),
+    #[command(name = "open", about = "Opens the URLs and files a task refers to")]
+    OpenTask(OpenTaskArgs),
+    #[command(name = "wait-for", about = "Marks a task as waiting on someone")]
+    WaitFor(WaitForArgs),
+    #[command(name = "snooze", about = "Pushes a task's deadline back")]
+    SnoozeTask(SnoozeTaskArgs),
+    #[command(name = "depend", about = "Makes a task wait on other tasks")]
+    DependTask(DependTaskArgs),
     #[command(name = "list", about = "Show tasks")]
     ListTasks(ListTasksArgs),
+    #[command(name = "archive", about = "Moves closed tasks to the archive file")]
+    ArchiveTasks(ArchiveTasksArgs),
+    #[command(
+        name = "export",
+        about = "Prints every task in a format other tools read"
+    )]
+    ExportTasks(ExportTasksArgs),
+    #[command(name = "import", about = "Adds tasks written by other tools")]
+    ImportTasks(ImportTasksArgs),
+    #[command(
+        name = "convert",
+        about = "Copies a task file and its archive to another format"
+    )]
+    Convert(ConvertArgs),
+    #[command(name = "serve", about = "Serves the tasks over a local HTTP API")]
+    Serve(ServeArgs),
+    #[command(
+        name = "daemon",
+        about = "Owns the task file and serves it to other commands over a Unix socket"
+    )]
+    Daemon,
+    #[command(name = "next", about = "Recommends the most urgent tasks to work on")]
+    NextTask(NextTaskArgs),
+    #[command(name = "start", about = "Starts a timer on a task, stopping any other")]
+    StartTask(StartTaskArgs),
+    #[command(name = "stop", about = "Stops the running timer")]
+    StopTask,
+    #[command(subcommand, name = "report", about = "Summarizes tracked work")]
+    Report(ReportCommands),
 }
 
-fn main() {
+/// Checks `key=value` assignments against the custom fields declared in the config
+fn parse_fields(
+    config: &Config,
+    assignments: &[String],
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    if assignments.is_empty() {
+        return Ok(Vec::new());
+    }
+    let definitions = FieldDefinitions::try_from(config)?;
+    assignments
+        .iter()
+        .map(|assignment| definitions.parse(assignment).map_err(Into::into))
+        .collect()
+}
+
+/// The task a command names by `id`, or by uid when `uid` is passed
+fn task_ref(id: usize, uid: bool) -> TaskRef {
+    match uid {
+        true => TaskRef::Uid(id),
+        false => TaskRef::Id(id),
+    }
+}
+
+/// Which tasks `list` shows, checking the options against the config file
+fn list_filter(config: &Config, args: ListTasksArgs) -> Result<ListFilter, Box<dyn Error>> {
+    let snoozed_often = match args.snoozed_often {
+        true => Some(config.get_or("snooze.often", 3)?),
+        false => None,
+    };
+    let assignee = match (args.mine, config.get("user")) {
+        (true, Some(user)) => Some(user.to_string()),
+        (true, None) => return Err("--mine needs `user` to be set in the config file".into()),
+        (false, _) => None,
+    };
+    Ok(ListFilter {
+        all: args.all,
+        overdue: args.overdue,
+        tags: args.tags,
+        ready: args.ready,
+        deferred: args.deferred,
+        scheduled: args.scheduled.map(|day| (day.start(), day.end())),
+        snoozed_often,
+        assignee,
+        waiting: args.waiting,
+        fields: parse_fields(config, &args.fields)?,
+        columns: args.columns,
+        archived: args.archived,
+        search: args.search,
+    })
+}
+
+/// The format given, or else the one the file's extension names, or else the one in the
+/// environment, or else the table format
+fn resolve_file_format(
+    given: Option<FileFormat>,
+    path: &Path,
+    env_var: Option<&str>,
+) -> Result<FileFormat, String> {
+    match (given.or(FileFormat::from_extension(path)), env_var) {
+        (Some(format), _) => Ok(format),
+        (None, Some(var)) => var
+            .parse()
+            .map_err(|e| format!("ON_JOB_FILE_FORMAT is invalid: {e}")),
+        (None, None) => Ok(FileFormat::default()),
+    }
+}
+
+/// Writes every task of one task file to another in its own format, then reads them back to
+/// make sure none were lost
+fn convert(args: ConvertArgs, env_var: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let from_format = resolve_file_format(args.from_format, &args.from, env_var)?;
+    let to_format = resolve_file_format(args.to_format, &args.to, env_var)?;
+    let source = open_tracker(&args.from, from_format, None, daemon_storage(&args.from));
+    let mut destination = open_tracker(&args.to, to_format, None, daemon_storage(&args.to));
+    if !source.exists()? {
+        return Err(format!("{} does not exist", args.from.display()).into());
+    }
+    if destination.exists()? && !args.force {
+        return Err(format!(
+            "{} already exists, pass --force to overwrite it",
+            args.to.display()
+        )
+        .into());
+    }
+
+    let set = source.read_all()?;
+    // Written to memory and read back first, so a format losing information never replaces what
+    // is at the destination
+    let mut trial = open_tracker(&args.to, to_format, None, Some(Box::new(Memory::default())));
+    trial.write_all(&set)?;
+    let differences = task_tracker::convert::differences(&set, &trial.read_all()?);
+    if !differences.is_empty() && !args.allow_loss {
+        return Err(format!(
+            "{} was not written, {to_format} cannot keep all of it, pass --allow-loss to write it \
+             anyway:\n  {}",
+            args.to.display(),
+            differences.join("\n  ")
+        )
+        .into());
+    }
+    destination.write_all(&set)?;
+    destination.storage().commit()?;
+    for difference in &differences {
+        eprintln!("Lost: {difference}");
+    }
+    println!(
+        "Converted {} task(s) and {} archived task(s) from {} ({from_format}) to {} ({to_format})",
+        set.tasks.len(),
+        set.archived.len(),
+        args.from.display(),
+        args.to.display()
+    );
+    Ok(())
+}
+
+/// The tracker keeping tasks at `path` in `format`
+fn open_tracker(
+    path: &Path,
+    format: FileFormat,
+    auto_archive: Option<TimeDelta>,
+    storage: Option<Box<dyn Storage>>,
+) -> Box<dyn TaskTracker<Err = PlainTextTaskTrackerError>> {
+    fn boxed<F: TaskFileFormat + 'static>(
+        tracker: PlainTextTaskTracker<F>,
+        auto_archive: Option<TimeDelta>,
+        storage: Option<Box<dyn Storage>>,
+    ) -> Box<dyn TaskTracker<Err = PlainTextTaskTrackerError>> {
+        let tracker = tracker.with_auto_archive(auto_archive);
+        match storage {
+            Some(storage) => Box::new(tracker.with_storage(storage)),
+            None => Box::new(tracker),
+        }
+    }
+    match format {
+        FileFormat::Table => boxed(
+            PlainTextTaskTracker::<PipeTable>::new(path),
+            auto_archive,
+            storage,
+        ),
+        FileFormat::TodoTxt => boxed(TodoTxtTaskTracker::new(path), auto_archive, storage),
+        FileFormat::Json => boxed(JsonTaskTracker::new(path), auto_archive, storage),
+        FileFormat::Yaml => boxed(YamlTaskTracker::new(path), auto_archive, storage),
+        FileFormat::Toml => boxed(TomlTaskTracker::new(path), auto_archive, storage),
+    }
+}
+
+/// The daemon owning the task file at `path`, when one is running
+#[cfg(unix)]
+fn daemon_storage(path: &Path) -> Option<Box<dyn Storage>> {
+    let socket = daemon::socket_path(path);
+    match daemon::DaemonStorage::connect(&socket) {
+        Ok(storage) => storage.map(|storage| Box::new(storage) as Box<dyn Storage>),
+        Err(e) => {
+            eprintln!(
+                "Warning: reading {} directly, the daemon at {} is not answering: {e}",
+                path.display(),
+                socket.display()
+            );
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn daemon_storage(_path: &Path) -> Option<Box<dyn Storage>> {
+    None
+}
+
+/// Carries out one command, committing its changes only once it succeeded
+fn run(args: Args) -> Result<(), PlainTextTaskTrackerError> {
     const ENV_KEY: &str = "ON_JOB_FILE";
-    let args = Args::parse();
+    const CONFIG_ENV_KEY: &str = "ON_JOB_CONFIG";
+    const FILE_FORMAT_ENV_KEY: &str = "ON_JOB_FILE_FORMAT";
+    let file_format_env = env::var(FILE_FORMAT_ENV_KEY).ok();
+    // Converting names both of its files, so it needs no task file of its own
+    if let Commands::Convert(convert_args) = args.command {
+        if let Err(e) = convert(convert_args, file_format_env.as_deref()) {
+            eprintln!("Error: {e}");
+        }
+        return Ok(());
+    }
     let file_path = match (args.file.as_ref(), env::var(ENV_KEY)) {
         (Some(val), _) => val,
         (_, Ok(var)) => &PathBuf::from(var),
         (None, Err(e)) => panic!("Error reading task file: {e}"),
     };
+    let config = match (args.config, env::var(CONFIG_ENV_KEY)) {
+        (Some(path), _) => Config::load(&path),
+        (None, Ok(var)) => Config::load(&PathBuf::from(var)),
+        (None, Err(_)) => Ok(Config::default()),
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error reading config file: {e}");
+            return Ok(());
+        }
+    };
 
-    let mut plain_text_tracker =
-        task_tracker::plain_text_task_tracker::PlainTextTaskTracker::new(file_path);
+    let auto_archive = match config.get("archive.after") {
+        Some(after) => match after.parse::<DurationWrapper>() {
+            Ok(after) => Some(after.0),
+            Err(_) => {
+                let e = ConfigError::InvalidValue("archive.after".into(), after.into());
+                eprintln!("Error reading config file: {e}");
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    let file_format =
+        match resolve_file_format(args.file_format, file_path, file_format_env.as_deref()) {
+            Ok(format) => format,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                return Ok(());
+            }
+        };
+    // The daemon itself reads the files directly, every other command goes through it if it runs
+    let storage = match args.command {
+        Commands::Daemon => None,
+        _ => daemon_storage(file_path),
+    };
+    let mut tracker = open_tracker(file_path, file_format, auto_archive, storage);
 
     let res = match args.command {
         Commands::AddTask(AddTaskArgs {
             name,
             tags,
             deadline,
-        }) => plain_text_tracker.add_task(name, tags, deadline.0.into()),
-        Commands::CompleteTask(CompleteTaskArgs { id }) => plain_text_tracker.complete_task(id),
-        Commands::DeleteTask(DeleteTaskArgs { id }) => plain_text_tracker.delete_task(id),
-        Commands::ListTasks(ListTasksArgs { all, overdue, tags }) => {
-            plain_text_tracker.list_task(all, overdue, tags)
+            parent,
+            depends_on,
+            priority,
+            estimate,
+            scheduled,
+            wait,
+            assign,
+            fields,
+            link,
+            note,
+        }) => match parse_fields(&config, &fields) {
+            Ok(fields) => tracker
+                .add_task(NewTask {
+                    name,
+                    tags,
+                    deadline: deadline.0.into(),
+                    parent,
+                    depends_on,
+                    priority,
+                    estimate,
+                    scheduled: scheduled.map(|scheduled| scheduled.0.into()),
+                    wait: wait.map(|wait| wait.0.into()),
+                    assignee: assign,
+                    fields,
+                    links: link.into_iter().map(|link| link.0).collect(),
+                    notes: note,
+                })
+                .map(|_| ()),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                return Ok(());
+            }
+        },
+        Commands::CompleteTask(CompleteTaskArgs { selection, cascade }) => {
+            let yes = selection.yes;
+            tracker.complete_task(selection.into(), cascade, yes)
+        }
+        Commands::DeleteTask(DeleteTaskArgs { selection }) => {
+            let yes = selection.yes;
+            tracker.delete_task(selection.into(), yes)
+        }
+        Commands::EditTask(EditTaskArgs {
+            selection,
+            name,
+            deadline,
+            add_tag,
+            remove_tag,
+            priority,
+            assign,
+            fields,
+        }) => match parse_fields(&config, &fields) {
+            Ok(fields) => {
+                let yes = selection.yes;
+                tracker.edit_task(
+                    selection.into(),
+                    TaskEdit {
+                        name,
+                        deadline: deadline.map(|deadline| deadline.0.into()),
+                        add_tags: add_tag,
+                        remove_tags: remove_tag,
+                        priority,
+                        assignee: assign,
+                        fields,
+                    },
+                    yes,
+                )
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                return Ok(());
+            }
+        },
+        Commands::SetStatus(SetStatusArgs { id, status, uid }) => {
+            tracker.set_status(task_ref(id, uid), status)
+        }
+        Commands::Check(CheckArgs {
+            command: Some(CheckCommands::Add(CheckAddArgs { id, text })),
+            ..
+        }) => tracker.add_checklist_item(id, text),
+        Commands::Check(CheckArgs {
+            id: Some(id),
+            item: Some(item),
+            uncheck,
+            ..
+        }) => tracker.check_item(id, item, !uncheck),
+        Commands::Check(_) => unreachable!("clap requires an id and item without a subcommand"),
+        Commands::ShowTask(ShowTaskArgs { id, uid, output }) => {
+            tracker.show_task(task_ref(id, uid), output)
         }
+        Commands::LinkTask(LinkTaskArgs { id, links }) => {
+            tracker.add_links(id, links.into_iter().map(|link| link.0).collect())
+        }
+        Commands::OpenTask(OpenTaskArgs { id, print }) => tracker.open_task(id, print),
+        Commands::WaitFor(WaitForArgs { id, who }) => tracker.wait_for(id, who),
+        Commands::SnoozeTask(args) => {
+            let yes = args.yes;
+            match args.split() {
+                Ok((selection, snooze)) => tracker.snooze_task(selection, snooze, yes),
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    return Ok(());
+                }
+            }
+        }
+        Commands::DependTask(DependTaskArgs { id, on }) => tracker.add_dependencies(id, on),
+        Commands::ListTasks(list_args) => match list_filter(&config, list_args) {
+            Ok(filter) => tracker.list_task(filter),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                return Ok(());
+            }
+        },
+        Commands::ArchiveTasks(ArchiveTasksArgs { older_than }) => {
+            tracker.archive_tasks(older_than.map(|older_than| older_than.0))
+        }
+        Commands::ExportTasks(ExportTasksArgs {
+            format,
+            events,
+            columns,
+        }) => tracker.export_tasks(format, ExportOptions { events, columns }),
+        Commands::ImportTasks(ImportTasksArgs {
+            format,
+            file,
+            mapping,
+        }) => {
+            let input = match file.to_str() {
+                Some("-") => std::io::read_to_string(std::io::stdin()),
+                _ => std::fs::read_to_string(&file),
+            };
+            match input {
+                Ok(input) => {
+                    let mapping = mapping
+                        .into_iter()
+                        .map(|MappingWrapper(header, column)| (header, column))
+                        .collect();
+                    tracker.import_tasks(format, &input, ImportOptions { mapping })
+                }
+                Err(e) => {
+                    eprintln!("Error reading {}: {e}", file.display());
+                    return Ok(());
+                }
+            }
+        }
+        Commands::Convert(_) => unreachable!("convert runs before a task file is opened"),
+        Commands::Serve(ServeArgs { bind }) => match tiny_http::Server::http(&bind) {
+            Ok(http) => {
+                let address = http
+                    .server_addr()
+                    .to_ip()
+                    .map_or(bind, |address| address.to_string());
+                println!("Serving {} on http://{address}", file_path.display());
+                server::serve(&http, tracker.as_mut(), &config);
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("Error: cannot listen on {bind}: {e}");
+                return Ok(());
+            }
+        },
+        #[cfg(unix)]
+        Commands::Daemon => {
+            let socket = daemon::socket_path(file_path);
+            match daemon::listen(&socket) {
+                Ok(listener) => {
+                    println!("Serving {} on {}", file_path.display(), socket.display());
+                    daemon::serve(&listener, tracker.as_ref());
+                    Ok(())
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    return Ok(());
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        Commands::Daemon => {
+            eprintln!("Error: the daemon needs Unix domain sockets");
+            return Ok(());
+        }
+        Commands::StartTask(StartTaskArgs { id }) => tracker.start_task(id),
+        Commands::StopTask => tracker.stop_task(),
+        Commands::Report(ReportCommands::Time(TimeReportArgs { since, by })) => {
+            tracker.time_report(since.map(|since| since.start()), by)
+        }
+        Commands::Report(ReportCommands::Estimates(EstimateReportArgs { since, until })) => tracker
+            .estimate_report(
+                since.map(|since| since.start()),
+                until.map(|until| until.end()),
+            ),
+        Commands::Report(ReportCommands::Load(LoadReportArgs { available })) => {
+            match available.map_or_else(|| config.get_or("load.available_hours", 40.0), Ok) {
+                Ok(hours) => {
+                    tracker.load_report(TimeDelta::seconds((hours * 3600.0).round() as i64))
+                }
+                Err(e) => {
+                    eprintln!("Error reading config file: {e}");
+                    return Ok(());
+                }
+            }
+        }
+        Commands::NextTask(NextTaskArgs { count }) => match UrgencyWeights::try_from(&config) {
+            Ok(weights) => tracker.next_task(count, &weights),
+            Err(e) => {
+                eprintln!("Error reading config file: {e}");
+                return Ok(());
+            }
+        },
     };
 
     match res {
-        Ok(()) => (),
-        Err(e) => eprintln!("Process failed: {e}"),
+        Ok(()) => Ok(tracker.storage().commit()?),
+        Err(e) => {
+            tracker.storage().rollback();
+            Err(e)
+        }
+    }
+}
+
+fn main() {
+    // Through the daemon a command fails without changing anything when another one changed what
+    // it read, it then runs again on what is there now
+    const ATTEMPTS: usize = 100;
+    for _ in 0..ATTEMPTS {
+        match run(Args::parse()) {
+            Ok(()) => return,
+            Err(e) if e.is_conflict() => {
+                eprintln!("The task file changed meanwhile, running the command again");
+            }
+            Err(e) => {
+                eprintln!("Process failed: {e}");
+                return;
+            }
+        }
+    }
+    eprintln!("Process failed: the task file kept changing, try again later");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use task_tracker::{TaskSet, storage::TaskFile, task::Task};
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("on_job_convert_{}_{name}", std::process::id()))
+    }
+
+    fn convert_args(from: &Path, to: &Path, force: bool, allow_loss: bool) -> ConvertArgs {
+        ConvertArgs {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            from_format: None,
+            to_format: None,
+            force,
+            allow_loss,
+        }
+    }
+
+    fn task(name: &str, uid: usize, status: Status) -> Task {
+        let deadline = Utc.with_ymd_and_hms(2025, 3, 14, 17, 0, 0).unwrap();
+        Task {
+            uid: Some(uid),
+            status,
+            ..Task::new(name.to_owned(), Some(vec!["work".to_owned()]), deadline)
+        }
+    }
+
+    fn read(path: &Path) -> TaskSet {
+        let format = resolve_file_format(None, path, None).unwrap();
+        open_tracker(path, format, None, None).read_all().unwrap()
+    }
+
+    fn remove(path: &Path) {
+        let tracker = open_tracker(path, FileFormat::Table, None, None);
+        for file in [TaskFile::Tasks, TaskFile::Archive] {
+            let _ = tracker.storage().remove(file);
+        }
+    }
+
+    fn refused(args: &[&str]) -> String {
+        Args::try_parse_from([&["on_job"], args].concat())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn cli_refuses_text_breaking_the_task_file() {
+        assert!(refused(&["add", "-n", "x", "--assign", "a|b"]).contains("cannot contain '|'"));
+        assert!(refused(&["edit", "0", "--assign", "a\nb"]).contains("line breaks"));
+        assert!(refused(&["wait-for", "0", "Sam | Alex"]).contains("cannot contain '|'"));
+        assert!(Args::try_parse_from(["on_job", "wait-for", "0", "Sam/Alex"]).is_ok());
+        assert!(refused(&["check", "add", "0", "pack | charger"]).contains("cannot contain '|'"));
+        assert!(refused(&["check", "add", "0", "pack\r\ncharger"]).contains("line breaks"));
+        assert!(refused(&["add", "-n", "x", "--note", "a|b"]).contains("cannot contain '|'"));
+        assert!(refused(&["add", "-n", "x", "--note", "first\nsecond"]).contains("line breaks"));
+        assert!(refused(&["add", "-n", "ok\nbad line"]).contains("line breaks"));
+        assert!(refused(&["add", "-n", "x", "-t", "a|b"]).contains("cannot contain '|'"));
+        assert!(refused(&["edit", "0", "-n", "a|b"]).contains("cannot contain '|'"));
+        assert!(refused(&["edit", "0", "--add-tag", "a\rb"]).contains("line breaks"));
+        assert!(refused(&["link", "0", "https://a.b/\nx"]).contains("line breaks"));
+    }
+
+    #[test]
+    fn convert_table_with_archive_to_documents() {
+        let table = temp_path("tasks.txt");
+        let json = temp_path("tasks.json");
+        let yaml = temp_path("tasks.yaml");
+        let mut source = PlainTextTaskTracker::<PipeTable>::new(&table);
+        let mut done = task("File taxes", 3, Status::Done);
+        done.completed = Some(Utc.with_ymd_and_hms(2025, 3, 10, 9, 30, 0).unwrap());
+        source
+            .write_all(&TaskSet {
+                tasks: vec![
+                    task("Call plumber", 1, Status::Todo),
+                    task("Send invoice", 2, Status::InProgress),
+                ],
+                archived: vec![done],
+            })
+            .unwrap();
+        let expected = source.read_all().unwrap();
+
+        for destination in [&json, &yaml] {
+            convert(convert_args(&table, destination, false, false), None).unwrap();
+            let converted = read(destination);
+            assert_eq!(converted.tasks.len(), 2);
+            assert_eq!(converted.archived.len(), 1);
+            assert_eq!(converted, expected);
+        }
+
+        let error = convert(convert_args(&table, &json, false, false), None).unwrap_err();
+        assert!(error.to_string().contains("pass --force"));
+
+        let mut grown = source.read_all().unwrap();
+        grown.tasks.push(task("Book flights", 4, Status::Todo));
+        source.write_all(&grown).unwrap();
+        convert(convert_args(&table, &json, true, false), None).unwrap();
+        assert_eq!(read(&json).tasks.len(), 3);
+
+        for path in [&table, &json, &yaml] {
+            remove(path);
+        }
+    }
+
+    #[test]
+    fn convert_keeps_destination_when_format_loses_tasks_data() {
+        let table = temp_path("lossy.txt");
+        let todo_txt = temp_path("lossy.todo.txt");
+        let mut spaced = task("Fix the roof", 1, Status::Todo);
+        spaced.tags = Some(vec!["big job".to_owned()]);
+        PlainTextTaskTracker::<PipeTable>::new(&table)
+            .write_all(&TaskSet {
+                tasks: vec![spaced],
+                archived: vec![],
+            })
+            .unwrap();
+        fs::write(&todo_txt, "Keep me\n").unwrap();
+
+        let args = |allow_loss| ConvertArgs {
+            to_format: Some(FileFormat::TodoTxt),
+            ..convert_args(&table, &todo_txt, true, allow_loss)
+        };
+
+        let error = convert(args(false), None).unwrap_err();
+        assert!(error.to_string().contains("pass --allow-loss"));
+        assert_eq!(fs::read_to_string(&todo_txt).unwrap(), "Keep me\n");
+
+        convert(args(true), None).unwrap();
+        assert_ne!(fs::read_to_string(&todo_txt).unwrap(), "Keep me\n");
+
+        remove(&table);
+        remove(&todo_txt);
     }
 }
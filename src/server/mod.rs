@@ -0,0 +1,695 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{collections::BTreeMap, error::Error, fmt::Display, str::FromStr};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::config::Config;
+use crate::task_tracker::{
+    ListFilter, ListedTask, NewTask, TaskEdit, TaskRef, TaskTracker,
+    plain_text_task_tracker::PlainTextTaskTrackerError,
+    selection::{IdRange, Selection},
+    task::{self, Estimate, Priority, Status},
+};
+use crate::{DateTimeWrapper, LinkWrapper, ListTasksArgs, list_filter, parse_fields};
+
+type Tracker = dyn TaskTracker<Err = PlainTextTaskTrackerError>;
+
+/// Answers requests one at a time until the server shuts down, so no two changes to the task
+/// file ever overlap
+pub fn serve(server: &Server, tracker: &mut Tracker, config: &Config) {
+    for request in server.incoming_requests() {
+        if let Err(e) = answer(request, tracker, config) {
+            eprintln!("Error answering a request: {e}");
+        }
+    }
+}
+
+fn answer(mut request: Request, tracker: &mut Tracker, config: &Config) -> std::io::Result<()> {
+    let mut body = String::new();
+    let reply = match request.as_reader().read_to_string(&mut body) {
+        Ok(_) => check_headers(&request, &body)
+            .and_then(|()| commit(request.method(), request.url(), &body, tracker, config)),
+        Err(e) => Err(ServerError::BadRequest(e.to_string())),
+    };
+    let (status, body) = match reply {
+        Ok(reply) => reply,
+        Err(e) => (e.status(), Some(json!({ "error": e.to_string() }))),
+    };
+    match body {
+        Some(body) => {
+            let content_type =
+                Header::from_bytes("Content-Type", "application/json").expect("Hardcoded values");
+            request.respond(
+                Response::from_string(body.to_string())
+                    .with_status_code(status)
+                    .with_header(content_type),
+            )
+        }
+        None => request.respond(Response::empty(status)),
+    }
+}
+
+/// Routes a request and commits the changes it made, routing it again when the daemon refuses
+/// them because another client changed the task file meanwhile
+fn commit(method: &Method, url: &str, body: &str, tracker: &mut Tracker, config: &Config) -> Reply {
+    const ATTEMPTS: usize = 100;
+    let mut attempt = 1;
+    loop {
+        let reply = route(method, url, body, tracker, config).and_then(|reply| {
+            tracker
+                .storage()
+                .commit()
+                .map_err(PlainTextTaskTrackerError::from)?;
+            Ok(reply)
+        });
+        match reply {
+            Err(ServerError::Tracker(ref e)) if e.is_conflict() && attempt < ATTEMPTS => {
+                attempt += 1;
+            }
+            reply => {
+                tracker.storage().rollback();
+                return reply;
+            }
+        }
+    }
+}
+
+/// Refuses what a web page open in a browser could send: browsers add an `Origin` to the
+/// requests pages make, and only let them send JSON to another site after asking it first
+fn check_headers(request: &Request, body: &str) -> Result<(), ServerError> {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str())
+    };
+    if header("Origin").is_some() {
+        return Err(ServerError::Forbidden);
+    }
+    let json = header("Content-Type").is_some_and(|value| {
+        value
+            .split(';')
+            .next()
+            .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"))
+    });
+    match body.is_empty() || json {
+        true => Ok(()),
+        false => Err(ServerError::UnsupportedMediaType),
+    }
+}
+
+/// The status and JSON body answering a request
+type Reply = Result<(u16, Option<Value>), ServerError>;
+
+fn route(method: &Method, url: &str, body: &str, tracker: &mut Tracker, config: &Config) -> Reply {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let query = query_pairs(query)?;
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (Method::Get, ["tasks"]) => list(tracker, config, query),
+        (Method::Post, ["tasks"]) => add(tracker, config, body),
+        (Method::Patch, ["tasks", id]) => edit(tracker, config, parse_id(id)?, body),
+        (Method::Post, ["tasks", id, "complete"]) => complete(tracker, parse_id(id)?, query),
+        (Method::Delete, ["tasks", id]) => {
+            let id = parse_id(id)?;
+            uid_of(tracker, id)?;
+            tracker.delete_task(single(id), true)?;
+            Ok((204, None))
+        }
+        (_, ["tasks"] | ["tasks", _] | ["tasks", _, "complete"]) => {
+            Err(ServerError::MethodNotAllowed)
+        }
+        _ => Err(ServerError::NotFound),
+    }
+}
+
+/// `GET /tasks`, taking the options of `list` as query parameters
+fn list(tracker: &Tracker, config: &Config, query: Vec<(String, String)>) -> Reply {
+    let mut args = ListTasksArgs::default();
+    for (key, value) in query {
+        match key.as_str() {
+            "all" => args.all = parse_flag(&key, &value)?,
+            "overdue" => args.overdue = parse_flag(&key, &value)?,
+            "tags" => args.tags.get_or_insert_default().push(value),
+            "ready" => args.ready = parse_flag(&key, &value)?,
+            "deferred" => args.deferred = parse_flag(&key, &value)?,
+            "scheduled" => args.scheduled = Some(parse_value(&key, &value)?),
+            "snoozed-often" => args.snoozed_often = parse_flag(&key, &value)?,
+            "mine" => args.mine = parse_flag(&key, &value)?,
+            "waiting" => args.waiting = parse_flag(&key, &value)?,
+            "field" => args.fields.push(value),
+            "archived" => args.archived = parse_flag(&key, &value)?,
+            "search" => args.search = Some(value),
+            _ => return Err(ServerError::BadRequest(format!("unknown filter \"{key}\""))),
+        }
+    }
+    let filter = list_filter(config, args).map_err(ServerError::bad_request)?;
+    // No task has been added yet
+    if !tracker.exists()? {
+        return Ok((200, Some(json!([]))));
+    }
+    Ok((200, Some(to_json(&tracker.listed_tasks(filter)?))))
+}
+
+/// What `POST /tasks` takes, the options of `add` with times written as for `add` or in RFC 3339
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewTaskBody {
+    name: String,
+    tags: Option<Vec<String>>,
+    deadline: Option<String>,
+    parent: Option<usize>,
+    depends_on: Option<Vec<usize>>,
+    priority: Option<Priority>,
+    estimate: Option<Estimate>,
+    scheduled: Option<String>,
+    wait: Option<String>,
+    assignee: Option<String>,
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    #[serde(default)]
+    links: Vec<String>,
+    #[serde(default)]
+    notes: Vec<String>,
+}
+
+fn add(tracker: &Tracker, config: &Config, body: &str) -> Reply {
+    let body: NewTaskBody = parse_body(body)?;
+    check_text("name", [&body.name])?;
+    check_text("tags", body.tags.iter().flatten())?;
+    check_text("assignee", &body.assignee)?;
+    check_text("notes", &body.notes)?;
+    let deadline = match body.deadline {
+        Some(deadline) => parse_time("deadline", &deadline)?,
+        None => DateTimeWrapper::default().0.into(),
+    };
+    let links = body
+        .links
+        .iter()
+        .map(|link| parse_value::<LinkWrapper>("links", link).map(|link| link.0))
+        .collect::<Result<_, _>>()?;
+    let uid = tracker.add_task(NewTask {
+        name: body.name,
+        tags: body.tags,
+        deadline,
+        parent: body.parent,
+        depends_on: body.depends_on,
+        priority: body.priority,
+        estimate: body.estimate,
+        scheduled: parse_optional_time("scheduled", body.scheduled)?,
+        wait: parse_optional_time("wait", body.wait)?,
+        assignee: body.assignee,
+        fields: parse_field_map(config, &body.fields)?,
+        links,
+        notes: body.notes,
+    })?;
+    Ok((
+        201,
+        find_uid(tracker, Some(uid))?.map(|task| to_json(&task)),
+    ))
+}
+
+/// What `PATCH /tasks/{id}` takes, the options of `edit` and a new status as for `status`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaskEditBody {
+    name: Option<String>,
+    deadline: Option<String>,
+    #[serde(default)]
+    add_tags: Vec<String>,
+    #[serde(default)]
+    remove_tags: Vec<String>,
+    priority: Option<Priority>,
+    assignee: Option<String>,
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    status: Option<Status>,
+}
+
+fn edit(tracker: &mut Tracker, config: &Config, id: usize, body: &str) -> Reply {
+    let body: TaskEditBody = parse_body(body)?;
+    check_text("name", &body.name)?;
+    check_text("add_tags", &body.add_tags)?;
+    check_text("assignee", &body.assignee)?;
+    let uid = uid_of(tracker, id)?;
+    let edit = TaskEdit {
+        name: body.name,
+        deadline: parse_optional_time("deadline", body.deadline)?,
+        add_tags: body.add_tags,
+        remove_tags: body.remove_tags,
+        priority: body.priority,
+        assignee: body.assignee,
+        fields: parse_field_map(config, &body.fields)?,
+    };
+    tracker.edit_task(single(id), edit, true)?;
+    if let Some(status) = body.status {
+        // A new deadline can reorder the tasks, and with them their ids, but not their uids
+        let task = uid.map_or(TaskRef::Id(id), TaskRef::Uid);
+        tracker.set_status(task, status)?;
+    }
+    Ok(follow_up(find_uid(tracker, uid)?))
+}
+
+fn complete(tracker: &mut Tracker, id: usize, query: Vec<(String, String)>) -> Reply {
+    let mut cascade = false;
+    for (key, value) in query {
+        match key.as_str() {
+            "cascade" => cascade = parse_flag(&key, &value)?,
+            _ => return Err(ServerError::BadRequest(format!("unknown option \"{key}\""))),
+        }
+    }
+    let uid = uid_of(tracker, id)?;
+    tracker.complete_task(single(id), cascade, true)?;
+    Ok(follow_up(find_uid(tracker, uid)?))
+}
+
+/// The changed task, or no body for a task from before uids existed, which can't be told apart
+/// from the others once its id changes
+fn follow_up(task: Option<ListedTask>) -> (u16, Option<Value>) {
+    match task {
+        Some(task) => (200, Some(to_json(&task))),
+        None => (204, None),
+    }
+}
+
+/// Every task of the task file, whatever its status or wait date
+fn every_task(tracker: &Tracker) -> Result<Vec<ListedTask>, ServerError> {
+    if !tracker.exists()? {
+        return Ok(Vec::new());
+    }
+    Ok(tracker.listed_tasks(ListFilter {
+        all: true,
+        deferred: true,
+        ..Default::default()
+    })?)
+}
+
+/// The uid of the task with `id`, which stays the same as other tasks come and go
+fn uid_of(tracker: &Tracker, id: usize) -> Result<Option<usize>, ServerError> {
+    every_task(tracker)?
+        .into_iter()
+        .find(|listed| listed.id == Some(id))
+        .map(|listed| listed.task.uid)
+        .ok_or(ServerError::Tracker(
+            PlainTextTaskTrackerError::TaskNotFound(id),
+        ))
+}
+
+fn find_uid(tracker: &Tracker, uid: Option<usize>) -> Result<Option<ListedTask>, ServerError> {
+    let Some(uid) = uid else {
+        return Ok(None);
+    };
+    Ok(every_task(tracker)?
+        .into_iter()
+        .find(|listed| listed.task.uid == Some(uid)))
+}
+
+fn single(id: usize) -> Selection {
+    Selection {
+        ids: vec![IdRange(id..=id)],
+        conditions: Vec::new(),
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("Tasks always serialize")
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, ServerError> {
+    serde_json::from_str(body).map_err(ServerError::bad_request)
+}
+
+/// Refuses text that would break the line of its task in the task file, as the CLI does
+fn check_text<'a>(
+    key: &str,
+    texts: impl IntoIterator<Item = &'a String>,
+) -> Result<(), ServerError> {
+    texts.into_iter().try_for_each(|text| {
+        task::check_text(text).map_err(|e| ServerError::BadRequest(format!("{key}: {e}")))
+    })
+}
+
+fn parse_id(id: &str) -> Result<usize, ServerError> {
+    id.parse()
+        .map_err(|_| ServerError::BadRequest(format!("\"{id}\" is not a task id")))
+}
+
+fn parse_value<T: FromStr<Err: Display>>(key: &str, value: &str) -> Result<T, ServerError> {
+    value
+        .parse()
+        .map_err(|e| ServerError::BadRequest(format!("{key}: {e}")))
+}
+
+/// A query parameter switching an option on, given alone, or as `true` or `false`
+fn parse_flag(key: &str, value: &str) -> Result<bool, ServerError> {
+    match value {
+        "" | "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(ServerError::BadRequest(format!(
+            "{key}: \"{value}\" is neither true nor false"
+        ))),
+    }
+}
+
+fn parse_time(key: &str, value: &str) -> Result<DateTime<Utc>, ServerError> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => Ok(time.to_utc()),
+        Err(_) => parse_value::<DateTimeWrapper>(key, value).map(|time| time.0.into()),
+    }
+}
+
+fn parse_optional_time(
+    key: &str,
+    value: Option<String>,
+) -> Result<Option<DateTime<Utc>>, ServerError> {
+    value.map(|value| parse_time(key, &value)).transpose()
+}
+
+/// Custom fields given as a JSON object, checked against the config file like `--field`
+fn parse_field_map(
+    config: &Config,
+    fields: &BTreeMap<String, String>,
+) -> Result<Vec<(String, String)>, ServerError> {
+    let assignments: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    parse_fields(config, &assignments).map_err(ServerError::bad_request)
+}
+
+/// The decoded `key=value` pairs of a query string
+fn query_pairs(query: &str) -> Result<Vec<(String, String)>, ServerError> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((decode(key)?, decode(value)?))
+        })
+        .collect()
+}
+
+/// Undoes the percent-encoding of a query string, where `+` stands for a space
+fn decode(s: &str) -> Result<String, ServerError> {
+    let invalid = || ServerError::BadRequest(format!("\"{s}\" is not validly encoded"));
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.bytes();
+    while let Some(byte) = rest.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [
+                    rest.next().ok_or_else(invalid)?,
+                    rest.next().ok_or_else(invalid)?,
+                ];
+                std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(invalid)?
+            }
+            byte => byte,
+        });
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[derive(Debug)]
+pub enum ServerError {
+    BadRequest(String),
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    UnsupportedMediaType,
+    Tracker(PlainTextTaskTrackerError),
+}
+
+impl ServerError {
+    fn bad_request(e: impl Display) -> Self {
+        ServerError::BadRequest(e.to_string())
+    }
+
+    fn status(&self) -> u16 {
+        match *self {
+            ServerError::BadRequest(_) => 400,
+            ServerError::Forbidden => 403,
+            ServerError::NotFound => 404,
+            ServerError::MethodNotAllowed => 405,
+            ServerError::UnsupportedMediaType => 415,
+            ServerError::Tracker(
+                PlainTextTaskTrackerError::TaskNotFound(_)
+                | PlainTextTaskTrackerError::UidNotFound(_)
+                | PlainTextTaskTrackerError::NothingSelected
+                | PlainTextTaskTrackerError::ChecklistItemNotFound(..),
+            ) => 404,
+            ServerError::Tracker(PlainTextTaskTrackerError::DependencyCycle(_)) => 409,
+            ServerError::Tracker(PlainTextTaskTrackerError::SnoozeTooFar(_)) => 400,
+            ServerError::Tracker(ref e) if e.is_conflict() => 409,
+            ServerError::Tracker(_) => 500,
+        }
+    }
+}
+
+impl Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ServerError::BadRequest(ref reason) => reason.fmt(f),
+            ServerError::Forbidden => "requests from web pages are not accepted".fmt(f),
+            ServerError::NotFound => "no such endpoint".fmt(f),
+            ServerError::MethodNotAllowed => "method not allowed on this endpoint".fmt(f),
+            ServerError::UnsupportedMediaType => "the body must be application/json".fmt(f),
+            ServerError::Tracker(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<PlainTextTaskTrackerError> for ServerError {
+    fn from(value: PlainTextTaskTrackerError) -> Self {
+        ServerError::Tracker(value)
+    }
+}
+
+impl Error for ServerError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::plain_text_task_tracker::{PipeTable, PlainTextTaskTracker};
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::path::PathBuf;
+
+    /// Serves a new task file on an ephemeral port
+    fn start(name: &str) -> (SocketAddr, PathBuf) {
+        let path =
+            std::env::temp_dir().join(format!("on_job_serve_{}_{name}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let http = Server::http("127.0.0.1:0").unwrap();
+        let address = http.server_addr().to_ip().unwrap();
+        let file_path = path.clone();
+        std::thread::spawn(move || {
+            let mut tracker = PlainTextTaskTracker::<PipeTable>::new(file_path);
+            serve(&http, &mut tracker, &Config::default());
+        });
+        (address, path)
+    }
+
+    fn request(address: SocketAddr, method: &str, target: &str, body: &str) -> (u16, Value) {
+        let headers = match body.is_empty() {
+            true => "",
+            false => "Content-Type: application/json\r\n",
+        };
+        request_with(address, method, target, headers, body)
+    }
+
+    fn request_with(
+        address: SocketAddr,
+        method: &str,
+        target: &str,
+        headers: &str,
+        body: &str,
+    ) -> (u16, Value) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{method} {target} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{headers}\
+             Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap_or(Value::Null))
+    }
+
+    fn names(tasks: &Value) -> Vec<&str> {
+        tasks
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| task["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn serve_changes_tasks() {
+        let (address, path) = start("changes");
+        let (status, task) = request(
+            address,
+            "POST",
+            "/tasks",
+            r#"{"name": "Call Acme", "tags": ["work"], "deadline": "2025-03-10T16:00:00Z"}"#,
+        );
+        assert_eq!(status, 201);
+        assert_eq!(task["id"], 0);
+        assert_eq!(task["uid"], 1);
+        let (status, _) = request(
+            address,
+            "POST",
+            "/tasks",
+            r#"{"name": "Fix the roof", "tags": ["home"], "deadline": "2025-03-12 17:00",
+                "priority": "high"}"#,
+        );
+        assert_eq!(status, 201);
+
+        let (status, tasks) = request(address, "GET", "/tasks?tags=home", "");
+        assert_eq!(status, 200);
+        assert_eq!(names(&tasks), vec!["Fix the roof"]);
+        assert_eq!(tasks[0]["id"], 1);
+        assert_eq!(tasks[0]["priority"], "high");
+        let (_, tasks) = request(address, "GET", "/tasks?search=call+acme", "");
+        assert_eq!(names(&tasks), vec!["Call Acme"]);
+
+        // Moving the first task past the second swaps their ids
+        let (status, task) = request(
+            address,
+            "PATCH",
+            "/tasks/0",
+            r#"{"deadline": "2025-03-20T16:00:00Z", "add_tags": ["q1"], "status": "in-progress"}"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(task["id"], 1);
+        assert_eq!(task["tags"], json!(["work", "q1"]));
+        assert_eq!(task["status"], "in-progress");
+
+        let (status, task) = request(address, "POST", "/tasks/1/complete", "");
+        assert_eq!(status, 200);
+        assert_eq!(task["name"], "Call Acme");
+        assert_eq!(task["status"], "done");
+        assert_eq!(task["id"], Value::Null);
+        let (_, tasks) = request(address, "GET", "/tasks", "");
+        assert_eq!(names(&tasks), vec!["Fix the roof"]);
+        let (_, tasks) = request(address, "GET", "/tasks?all=true", "");
+        assert_eq!(names(&tasks), vec!["Fix the roof", "Call Acme"]);
+
+        let (status, _) = request(address, "DELETE", "/tasks/0", "");
+        assert_eq!(status, 204);
+        let (_, tasks) = request(address, "GET", "/tasks?all", "");
+        assert_eq!(names(&tasks), vec!["Call Acme"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn serve_rejects_bad_requests() {
+        let (address, path) = start("rejects");
+        let (status, body) = request(address, "GET", "/tasks", "");
+        assert_eq!((status, body), (200, json!([])));
+        let (status, body) = request(address, "POST", "/tasks", r#"{"tags": ["work"]}"#);
+        assert_eq!(status, 400);
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .contains("missing field `name`"),
+            "{body}"
+        );
+        let (status, _) = request(
+            address,
+            "POST",
+            "/tasks",
+            r#"{"name": "x", "deadline": "soon"}"#,
+        );
+        assert_eq!(status, 400);
+        let (status, body) = request(address, "GET", "/tasks?colour=red", "");
+        assert_eq!(
+            (status, body),
+            (400, json!({"error": "unknown filter \"colour\""}))
+        );
+        let (status, body) = request(address, "GET", "/tasks?field=ticket%3DOPS-1", "");
+        assert_eq!(status, 400, "{body}");
+        let (status, body) = request(address, "DELETE", "/tasks/3", "");
+        assert_eq!((status, body), (404, json!({"error": "no task with id 3"})));
+        assert_eq!(request(address, "PATCH", "/tasks/two", "{}").0, 400);
+        assert_eq!(request(address, "PUT", "/tasks", "").0, 405);
+        assert_eq!(request(address, "GET", "/projects", "").0, 404);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn serve_rejects_text_breaking_the_task_file() {
+        let (address, path) = start("text");
+        for body in [
+            r#"{"name": "ok\nbad line"}"#,
+            r#"{"name": "ok", "notes": ["x|y"]}"#,
+            r#"{"name": "ok", "tags": ["a\rb"]}"#,
+            r#"{"name": "ok", "assignee": "a|b"}"#,
+            r#"{"name": "ok", "links": ["https://a.b/\nx"]}"#,
+        ] {
+            let (status, error) = request(address, "POST", "/tasks", body);
+            assert_eq!(status, 400, "{body}: {error}");
+        }
+        let (status, body) = request(address, "POST", "/tasks", r#"{"name": "Call Acme"}"#);
+        assert_eq!(status, 201, "{body}");
+        for body in [
+            r#"{"name": "a|b"}"#,
+            r#"{"add_tags": ["a\nb"]}"#,
+            r#"{"assignee": "a\nb"}"#,
+        ] {
+            let (status, error) = request(address, "PATCH", "/tasks/0", body);
+            assert_eq!(status, 400, "{body}: {error}");
+        }
+        let (status, tasks) = request(address, "GET", "/tasks", "");
+        assert_eq!(status, 200);
+        assert_eq!(names(&tasks), vec!["Call Acme"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn serve_refuses_requests_web_pages_can_send() {
+        let (address, path) = start("browser");
+        let body = r#"{"name": "Call Acme"}"#;
+        let (status, error) = request_with(address, "POST", "/tasks", "", body);
+        assert_eq!(
+            (status, error),
+            (415, json!({"error": "the body must be application/json"}))
+        );
+        let text = "Content-Type: text/plain\r\n";
+        assert_eq!(request_with(address, "POST", "/tasks", text, body).0, 415);
+
+        let json = "Content-Type: application/json; charset=utf-8\r\n";
+        let origin = "Origin: https://example.com\r\n";
+        let (status, _) =
+            request_with(address, "POST", "/tasks", &(json.to_owned() + origin), body);
+        assert_eq!(status, 403);
+        assert_eq!(request_with(address, "GET", "/tasks", origin, "").0, 403);
+        assert_eq!(request_with(address, "POST", "/tasks", json, body).0, 201);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn query_strings_are_decoded() {
+        assert_eq!(
+            query_pairs("search=call+acme&field=client%3DAcme%2C%20Inc.&all").unwrap(),
+            vec![
+                ("search".into(), "call acme".into()),
+                ("field".into(), "client=Acme, Inc.".into()),
+                ("all".into(), String::new()),
+            ]
+        );
+        assert!(query_pairs("search=100%").is_err());
+        assert!(query_pairs("search=%zz").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, SecondsFormat};
+use serde_json::Value;
+
+use crate::task_tracker::TaskSet;
+use crate::task_tracker::task::Task;
+
+/// How the tasks read back after `convert` differ from the ones written, empty when nothing was
+/// lost. Times are compared to the second, the precision every format keeps
+pub fn differences(written: &TaskSet, read: &TaskSet) -> Vec<String> {
+    let mut differences = Vec::new();
+    for (what, written, read) in [
+        ("task", &written.tasks, &read.tasks),
+        ("archived task", &written.archived, &read.archived),
+    ] {
+        if written.len() != read.len() {
+            differences.push(format!(
+                "{} {what}(s) were written but {} read back",
+                written.len(),
+                read.len()
+            ));
+            continue;
+        }
+        for (written, read) in written.iter().zip(read) {
+            let fields = differing_fields(written, read);
+            if !fields.is_empty() {
+                differences.push(format!(
+                    "{what} \"{}\" differs in {}",
+                    written.name,
+                    fields.join(", ")
+                ));
+            }
+        }
+    }
+    differences
+}
+
+fn differing_fields(written: &Task, read: &Task) -> Vec<String> {
+    match (comparable(written), comparable(read)) {
+        (Value::Object(written), Value::Object(read)) => written
+            .into_iter()
+            .filter(|(field, value)| read.get(field) != Some(value))
+            .map(|(field, _)| field)
+            .collect(),
+        _ => unreachable!("Tasks serialize to objects"),
+    }
+}
+
+/// The task as JSON, with every time cut down to the second
+fn comparable(task: &Task) -> Value {
+    fn truncate(value: &mut Value) {
+        match value {
+            Value::String(text) => {
+                if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+                    *text = date_time
+                        .to_utc()
+                        .to_rfc3339_opts(SecondsFormat::Secs, true);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(truncate),
+            Value::Object(fields) => fields.values_mut().for_each(truncate),
+            _ => {}
+        }
+    }
+    let mut value = serde_json::to_value(task).expect("Tasks always serialize");
+    truncate(&mut value);
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+
+    fn task(name: &str, created: &str) -> Task {
+        Task {
+            name: name.into(),
+            deadline: DateTime::parse_from_rfc3339("2025-03-12T16:00:00Z")
+                .unwrap()
+                .to_utc(),
+            created: Some(DateTime::parse_from_rfc3339(created).unwrap().to_utc()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn differences_ignore_fractions_of_seconds() {
+        let written = TaskSet {
+            tasks: vec![task("Call Acme", "2025-03-01T09:30:12.345Z")],
+            archived: vec![task("Ship it", "2025-03-01T09:30:00Z")],
+        };
+        let read = TaskSet {
+            tasks: vec![task("Call Acme", "2025-03-01T09:30:12Z")],
+            archived: vec![task("Ship it", "2025-03-01T09:30:00Z")],
+        };
+        assert!(differences(&written, &read).is_empty());
+    }
+
+    #[test]
+    fn differences_name_what_was_lost() {
+        let written = TaskSet {
+            tasks: vec![
+                task("Call Acme", "2025-03-01T09:30:00Z"),
+                Task {
+                    tags: Some(vec!["big job".into()]),
+                    priority: "high".parse().ok(),
+                    ..task("Fix the roof", "2025-03-01T09:30:00Z")
+                },
+            ],
+            archived: vec![task("Ship it", "2025-03-01T09:30:00Z")],
+        };
+        let mut read = TaskSet {
+            tasks: vec![
+                task("Call Acme", "2025-03-01T00:00:00Z"),
+                Task {
+                    tags: Some(vec!["big_job".into()]),
+                    ..task("Fix the roof", "2025-03-01T09:30:00Z")
+                },
+            ],
+            archived: Vec::new(),
+        };
+        read.tasks[0].completed = Some(Utc::now());
+        assert_eq!(
+            differences(&written, &read),
+            vec![
+                "task \"Call Acme\" differs in completed, created",
+                "task \"Fix the roof\" differs in priority, tags",
+                "1 archived task(s) were written but 0 read back",
+            ]
+        );
+    }
+}
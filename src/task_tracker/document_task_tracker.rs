@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use crate::task_tracker::plain_text_task_tracker::{PlainTextTaskTracker, TaskFileFormat};
+use crate::task_tracker::task::{ParseTaskError, Task};
+use std::io::{BufRead, Write};
+
+/// Keeps tasks in a JSON document
+pub type JsonTaskTracker = PlainTextTaskTracker<Json>;
+/// Keeps tasks in a YAML document
+pub type YamlTaskTracker = PlainTextTaskTracker<Yaml>;
+/// Keeps tasks in a TOML document
+pub type TomlTaskTracker = PlainTextTaskTracker<Toml>;
+
+/// The whole task file, a `tasks` list of every field of every task. TOML documents have to be
+/// tables, so all three formats share this shape rather than being a bare list
+#[derive(Serialize, Deserialize)]
+struct TaskDocument<T> {
+    tasks: Vec<T>,
+}
+
+pub struct Json;
+pub struct Yaml;
+pub struct Toml;
+
+/// Reads a whole document, where an empty file holds no tasks yet
+fn read_document<B: BufRead>(
+    mut reader: B,
+    parse: impl FnOnce(&str) -> Result<TaskDocument<Task>, String>,
+) -> Result<Vec<Task>, ParseTaskError> {
+    let mut input = String::new();
+    reader
+        .read_to_string(&mut input)
+        .map_err(|e| ParseTaskError::InvalidDocument(e.to_string()))?;
+    if input.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut tasks = parse(&input)
+        .map_err(ParseTaskError::InvalidDocument)?
+        .tasks;
+    tasks.sort_by_key(|task| task.deadline);
+    Ok(tasks)
+}
+
+fn document<'a>(tasks: &[&'a Task]) -> TaskDocument<&'a Task> {
+    TaskDocument {
+        tasks: tasks.to_vec(),
+    }
+}
+
+impl TaskFileFormat for Json {
+    const APPENDABLE: bool = false;
+
+    fn read_tasks<B: BufRead>(reader: B) -> Result<Vec<Task>, ParseTaskError> {
+        read_document(reader, |input| {
+            serde_json::from_str(input).map_err(|e| e.to_string())
+        })
+    }
+
+    fn append_task<W: Write>(writer: &mut W, task: &Task) -> Result<(), std::io::Error> {
+        Self::write_tasks(writer, &[task])
+    }
+
+    fn write_tasks<W: Write>(writer: &mut W, tasks: &[&Task]) -> Result<(), std::io::Error> {
+        serde_json::to_writer_pretty(&mut *writer, &document(tasks))?;
+        writeln!(writer)
+    }
+}
+
+impl TaskFileFormat for Yaml {
+    const APPENDABLE: bool = false;
+
+    fn read_tasks<B: BufRead>(reader: B) -> Result<Vec<Task>, ParseTaskError> {
+        read_document(reader, |input| {
+            serde_yaml::from_str(input).map_err(|e| e.to_string())
+        })
+    }
+
+    fn append_task<W: Write>(writer: &mut W, task: &Task) -> Result<(), std::io::Error> {
+        Self::write_tasks(writer, &[task])
+    }
+
+    fn write_tasks<W: Write>(writer: &mut W, tasks: &[&Task]) -> Result<(), std::io::Error> {
+        serde_yaml::to_writer(writer, &document(tasks)).map_err(std::io::Error::other)
+    }
+}
+
+impl TaskFileFormat for Toml {
+    const APPENDABLE: bool = false;
+
+    fn read_tasks<B: BufRead>(reader: B) -> Result<Vec<Task>, ParseTaskError> {
+        read_document(reader, |input| {
+            toml::from_str(input).map_err(|e| e.to_string())
+        })
+    }
+
+    fn append_task<W: Write>(writer: &mut W, task: &Task) -> Result<(), std::io::Error> {
+        Self::write_tasks(writer, &[task])
+    }
+
+    fn write_tasks<W: Write>(writer: &mut W, tasks: &[&Task]) -> Result<(), std::io::Error> {
+        let output = toml::to_string_pretty(&document(tasks)).map_err(std::io::Error::other)?;
+        writer.write_all(output.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::exchange::test_util::date_time;
+    use crate::task_tracker::task::{ChecklistItem, Priority, Status, WorkInterval};
+
+    fn tasks() -> Vec<Task> {
+        let mut task = Task {
+            name: "Write report, then \"ship\" it | soon".into(),
+            tags: Some(vec!["work".into(), "q1".into()]),
+            deadline: date_time("2025-03-12T16:00:00Z"),
+            status: Status::InProgress,
+            uid: Some(3),
+            parent: Some(1),
+            depends_on: vec![1, 2],
+            priority: Some(Priority::High),
+            created: Some(date_time("2025-03-01T09:30:12.345Z")),
+            work_log: vec![WorkInterval {
+                start: date_time("2025-03-10T09:00:00Z"),
+                end: None,
+            }],
+            estimate: Some("1h30m".parse().unwrap()),
+            waiting_for: Some(("Sam".into(), date_time("2025-03-09T08:00:00Z"))),
+            links: vec!["https://example.com".into()],
+            checklist: vec![ChecklistItem {
+                text: "Draft".into(),
+                done: true,
+            }],
+            notes: vec!["Ask about the numbers".into()],
+            snoozed: 2,
+            ..Default::default()
+        };
+        task.fields.insert("client".into(), "Acme".into());
+        vec![
+            Task {
+                name: "Call Acme".into(),
+                deadline: date_time("2025-03-10T16:00:00Z"),
+                ..Default::default()
+            },
+            task,
+        ]
+    }
+
+    fn round_trip<F: TaskFileFormat>() -> (String, Vec<Task>) {
+        let tasks = tasks();
+        let mut output = Vec::new();
+        F::write_tasks(&mut output, &tasks.iter().collect::<Vec<_>>()).unwrap();
+        let read = F::read_tasks(output.as_slice()).unwrap();
+        assert_eq!(read, tasks);
+        (String::from_utf8(output).unwrap(), read)
+    }
+
+    #[test]
+    fn documents_keep_every_field() {
+        let (json, _) = round_trip::<Json>();
+        assert!(json.starts_with("{\n  \"tasks\": [\n"), "{json}");
+        let (yaml, _) = round_trip::<Yaml>();
+        assert!(yaml.starts_with("tasks:\n- name: Call Acme\n"), "{yaml}");
+        let (toml, _) = round_trip::<Toml>();
+        assert!(
+            toml.starts_with("[[tasks]]\nname = \"Call Acme\"\n"),
+            "{toml}"
+        );
+    }
+
+    #[test]
+    fn documents_leave_out_empty_fields() {
+        let json = r#"{"tasks": [{"name": "Call Acme", "deadline": "2025-03-10T16:00:00Z"}]}"#;
+        let yaml = "tasks:\n- name: Call Acme\n  deadline: 2025-03-10T16:00:00Z\n";
+        let toml = "[[tasks]]\nname = \"Call Acme\"\ndeadline = \"2025-03-10T16:00:00Z\"\n";
+        let expected = vec![tasks().remove(0)];
+        assert_eq!(Json::read_tasks(json.as_bytes()).unwrap(), expected);
+        assert_eq!(Yaml::read_tasks(yaml.as_bytes()).unwrap(), expected);
+        assert_eq!(Toml::read_tasks(toml.as_bytes()).unwrap(), expected);
+        assert_eq!(Json::read_tasks(" \n".as_bytes()).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn invalid_documents_say_why() {
+        let invalid = r#"{"tasks": [{"name": "Call Acme", "deadline": "2025-03-10T16:00:00Z", "status": "someday"}]}"#;
+        match Json::read_tasks(invalid.as_bytes()) {
+            Err(ParseTaskError::InvalidDocument(reason)) => {
+                assert!(reason.contains("\"someday\" is not one of"), "{reason}");
+                assert!(reason.contains("line 1"), "{reason}");
+            }
+            other => panic!("{other:?}"),
+        }
+        assert!(Toml::read_tasks("[[tasks]]\nname = \"Call Acme\"\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+use ::csv::{ReaderBuilder, StringRecord, Writer};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use crate::task_tracker::exchange::{ImportError, Imported, end_of_today, storable};
+use crate::task_tracker::task::{Task, end_of_day};
+
+/// Columns `export` writes unless told otherwise. The others are parent, depends, created,
+/// completed, scheduled, wait, notes and links, and any other column holds a custom field, like
+/// the `uuid` that tells tasks exported from the task file apart on import
+const DEFAULT_COLUMNS: [&str; 9] = [
+    "uid", "name", "tags", "status", "deadline", "priority", "estimate", "assignee", "uuid",
+];
+/// Times are written the way spreadsheets recognize them, in local time
+const DATE_TIME: &str = "%Y-%m-%d %H:%M";
+
+/// Writes a header row naming `columns`, then one row per task
+pub(super) fn export<Tz: TimeZone>(tasks: &[Task], columns: &[String], tz: &Tz) -> String {
+    let columns: Vec<&str> = match columns.is_empty() {
+        true => DEFAULT_COLUMNS.to_vec(),
+        false => columns.iter().map(String::as_str).collect(),
+    };
+    let mut writer = Writer::from_writer(Vec::new());
+    let rows = std::iter::once(columns.iter().map(|column| column.to_string()).collect())
+        .chain(tasks.iter().map(|task| to_row(task, &columns, tz)));
+    for row in rows {
+        writer
+            .write_record(row.iter().map(String::as_bytes))
+            .expect("Writing to memory cannot fail");
+    }
+    let bytes = writer.into_inner().expect("Writing to memory cannot fail");
+    String::from_utf8(bytes).expect("Every column is a String")
+}
+
+fn to_row<Tz: TimeZone>(task: &Task, columns: &[&str], tz: &Tz) -> Vec<String> {
+    let date = |date_time: &DateTime<Utc>| {
+        date_time
+            .with_timezone(tz)
+            .naive_local()
+            .format(DATE_TIME)
+            .to_string()
+    };
+    let list = |items: &[String], separator: &str| items.join(separator);
+    columns
+        .iter()
+        .map(|column| match *column {
+            "uid" => task.uid.map(|uid| uid.to_string()).unwrap_or_default(),
+            "name" => task.name.clone(),
+            "tags" => list(task.tags.as_deref().unwrap_or_default(), ", "),
+            "status" => task.status.to_string(),
+            "deadline" => date(&task.deadline),
+            "priority" => task.priority.map(|p| p.to_string()).unwrap_or_default(),
+            "estimate" => task.estimate.map(|e| e.to_string()).unwrap_or_default(),
+            "assignee" => task.assignee.clone().unwrap_or_default(),
+            "parent" => task.parent.map(|uid| uid.to_string()).unwrap_or_default(),
+            "depends" => task
+                .depends_on
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            "created" => task.created.as_ref().map(date).unwrap_or_default(),
+            "completed" => task.completed.as_ref().map(date).unwrap_or_default(),
+            "scheduled" => task.scheduled.as_ref().map(date).unwrap_or_default(),
+            "wait" => task.wait.as_ref().map(date).unwrap_or_default(),
+            // One per line, which a quoted cell keeps together
+            "notes" => list(&task.notes, "\n"),
+            "links" => list(&task.links, "\n"),
+            field => task.fields.get(field).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Reads rows under a header naming their columns, renamed by `mapping` from a header to a
+/// column, where an empty column drops the header. Rows that can't be read are rejected with
+/// the line they start on, and the rest are still imported
+pub(super) fn import<Tz: TimeZone>(
+    input: &str,
+    mapping: &[(String, String)],
+    tz: &Tz,
+    now: DateTime<Utc>,
+) -> Result<Imported, ImportError> {
+    let default_deadline = end_of_today(tz, now);
+
+    // Spreadsheets tend to start their exports with a byte order mark
+    let input = input.trim_start_matches('\u{feff}');
+    let mut reader = ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(input.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| ImportError::new(line_of(&e), e.to_string()))?;
+    let columns: Vec<String> = headers
+        .iter()
+        .map(|header| {
+            let header = header.trim();
+            mapping
+                .iter()
+                .find(|(from, _)| from.trim().eq_ignore_ascii_case(header))
+                .map_or(header, |(_, to)| to.trim())
+                .to_lowercase()
+        })
+        .collect();
+    if !columns.iter().any(|column| column == "name") {
+        return Err(ImportError::new(1, "no name column, map one with --map"));
+    }
+
+    let mut imported = Imported::default();
+    for record in reader.records() {
+        let row = record
+            .map_err(|e| ImportError::new(line_of(&e), e.to_string()))
+            .and_then(|record| from_row(&columns, &record, tz, default_deadline));
+        match row {
+            Ok(task) => imported.tasks.push(task),
+            Err(e) => imported.rejected.push(e),
+        }
+    }
+    Ok(imported)
+}
+
+fn line_of(e: &::csv::Error) -> usize {
+    e.position().map_or(1, |position| position.line() as usize)
+}
+
+fn from_row<Tz: TimeZone>(
+    columns: &[String],
+    record: &StringRecord,
+    tz: &Tz,
+    default_deadline: DateTime<Utc>,
+) -> Result<Task, ImportError> {
+    let line = record
+        .position()
+        .map_or(1, |position| position.line() as usize);
+    let mut task = Task {
+        deadline: default_deadline,
+        ..Default::default()
+    };
+    for (column, value) in columns.iter().zip(record.iter()) {
+        let value = value.trim();
+        if column.is_empty() || value.is_empty() {
+            continue;
+        }
+        let invalid = || ImportError::new(line, format!("\"{value}\" is not a valid {column}"));
+        let date = |time: NaiveTime| parse_date_time(value, time, tz).ok_or_else(invalid);
+        let list = |separator: char| {
+            value
+                .split(separator)
+                .map(storable)
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>()
+        };
+        match column.as_str() {
+            "uid" => task.uid = Some(value.parse().map_err(|_| invalid())?),
+            "name" => task.name = storable(value),
+            "tags" => task.tags = Some(list(',')).filter(|tags| !tags.is_empty()),
+            "status" => task.status = value.parse().map_err(|_| invalid())?,
+            "deadline" => task.deadline = date(end_of_day())?,
+            "priority" => task.priority = Some(value.parse().map_err(|_| invalid())?),
+            "estimate" => task.estimate = Some(value.parse().map_err(|_| invalid())?),
+            "assignee" => task.assignee = Some(storable(value)),
+            "parent" => task.parent = Some(value.parse().map_err(|_| invalid())?),
+            "depends" => {
+                task.depends_on = value
+                    .split(',')
+                    .map(|uid| uid.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid())?
+            }
+            "created" => task.created = Some(date(NaiveTime::MIN)?),
+            "completed" => task.completed = Some(date(NaiveTime::MIN)?),
+            "scheduled" => task.scheduled = Some(date(NaiveTime::MIN)?),
+            "wait" => task.wait = Some(date(NaiveTime::MIN)?),
+            "notes" => task.notes = list('\n'),
+            "links" => task.links = list('\n'),
+            field => {
+                // Field names end at `=` in the task file and at `:` in todo.txt
+                let name = field.split_whitespace().collect::<Vec<_>>().join("_");
+                task.fields
+                    .insert(name.replace(['=', '|', ':'], "_"), storable(value));
+            }
+        }
+    }
+    match task.name.is_empty() {
+        true => Err(ImportError::new(line, "a task needs a name")),
+        false => Ok(task),
+    }
+}
+
+/// Reads a time as spreadsheets write it, in `tz` unless it carries an offset; a date alone is
+/// taken to be at `time`
+fn parse_date_time<Tz: TimeZone>(value: &str, time: NaiveTime, tz: &Tz) -> Option<DateTime<Utc>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Some(date_time.to_utc());
+    }
+    let naive = ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(time))
+        })?;
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|date_time| date_time.to_utc())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::exchange::test_util::{date_time, tz};
+    use crate::task_tracker::task::{Priority, Status};
+
+    #[test]
+    fn export_quotes_what_needs_it() {
+        let mut task = Task {
+            name: "Plan Q3, then \"pitch\" it".into(),
+            tags: Some(vec!["work".into(), "planning".into()]),
+            deadline: date_time("2025-03-12T16:00:00+00:00"),
+            uid: Some(3),
+            priority: Some(Priority::High),
+            notes: vec!["Ask Sam".into(), "Book a room".into()],
+            ..Default::default()
+        };
+        task.fields.insert("client".into(), "Acme".into());
+        task.fields.insert("uuid".into(), "5d0f2c3e".into());
+        let tasks = [task];
+        assert_eq!(
+            export(&tasks, &[], &tz()),
+            "uid,name,tags,status,deadline,priority,estimate,assignee,uuid\n\
+             3,\"Plan Q3, then \"\"pitch\"\" it\",\"work, planning\",todo,2025-03-12 17:00,high,,,5d0f2c3e\n"
+        );
+        let columns = ["name", "notes", "client", "team"].map(String::from);
+        assert_eq!(
+            export(&tasks, &columns, &tz()),
+            "name,notes,client,team\n\
+             \"Plan Q3, then \"\"pitch\"\" it\",\"Ask Sam\nBook a room\",Acme,\n"
+        );
+    }
+
+    #[test]
+    fn import_round_trips_an_export() {
+        let mut task = Task {
+            name: "Plan Q3, then \"pitch\" it".into(),
+            tags: Some(vec!["work".into(), "planning".into()]),
+            deadline: date_time("2025-03-12T16:00:00+00:00"),
+            status: Status::Done,
+            uid: Some(3),
+            parent: Some(1),
+            depends_on: vec![1, 2],
+            priority: Some(Priority::High),
+            estimate: Some("2h".parse().unwrap()),
+            assignee: Some("Sam".into()),
+            completed: Some(date_time("2025-03-11T08:30:00+00:00")),
+            notes: vec!["Ask Sam".into(), "Book a room".into()],
+            links: vec!["https://example.com".into()],
+            ..Default::default()
+        };
+        task.fields.insert("client".into(), "Acme, Inc.".into());
+        let columns = [
+            "uid",
+            "name",
+            "tags",
+            "status",
+            "deadline",
+            "priority",
+            "estimate",
+            "assignee",
+            "parent",
+            "depends",
+            "created",
+            "completed",
+            "scheduled",
+            "wait",
+            "notes",
+            "links",
+            "client",
+        ]
+        .map(String::from);
+        let output = export(std::slice::from_ref(&task), &columns, &tz());
+        let imported = import(&output, &[], &tz(), date_time("2025-03-10T09:00:00+00:00")).unwrap();
+        assert_eq!(imported.tasks, vec![task]);
+        assert!(imported.rejected.is_empty());
+    }
+
+    #[test]
+    fn import_maps_columns_and_reports_failing_rows() {
+        let input = "\u{feff}Title,Due Date,Owner,Internal id,Notes\n\
+                     \"Call Acme, again\",2025-03-14,Sam,17,\"first\nsecond\"\n\
+                     ,2025-03-14,Sam,18,\n\
+                     Fix the roof,next week,Alex,19,\n\
+                     Pay | rent,2025-03-15 09:30,,20,\n";
+        let mapping = [
+            ("title".to_string(), "name".to_string()),
+            ("Due Date".into(), "deadline".into()),
+            ("Owner".into(), "assignee".into()),
+            ("Internal id".into(), "".into()),
+        ];
+        let imported = import(
+            input,
+            &mapping,
+            &tz(),
+            date_time("2025-03-10T09:00:00+00:00"),
+        )
+        .unwrap();
+        assert_eq!(
+            imported.tasks,
+            vec![
+                Task {
+                    name: "Call Acme, again".into(),
+                    deadline: date_time("2025-03-14T16:00:00+00:00"),
+                    assignee: Some("Sam".into()),
+                    notes: vec!["first".into(), "second".into()],
+                    ..Default::default()
+                },
+                Task {
+                    name: "Pay / rent".into(),
+                    deadline: date_time("2025-03-15T08:30:00+00:00"),
+                    ..Default::default()
+                },
+            ]
+        );
+        // Line numbers count the line break inside the quoted notes
+        assert_eq!(
+            imported.rejected,
+            vec![
+                ImportError::new(4, "a task needs a name"),
+                ImportError::new(5, "\"next week\" is not a valid deadline"),
+            ]
+        );
+
+        assert_eq!(
+            import("Title,Due\nCall Acme,2025-03-14\n", &[], &tz(), Utc::now()).err(),
+            Some(ImportError::new(1, "no name column, map one with --map"))
+        );
+    }
+}
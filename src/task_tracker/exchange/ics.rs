@@ -0,0 +1,444 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use crate::task_tracker::exchange::{
+    BASIC_DATE_TIME, ImportError, Imported, format_basic_date_time, storable,
+};
+use crate::task_tracker::task::{Estimate, Priority, Status, Task, end_of_day};
+
+/// Writes tasks as an iCalendar document of VTODO entries, adding a VEVENT at the deadline of
+/// every open task when `events` is set
+pub(super) fn export(tasks: &[Task], events: bool, now: DateTime<Utc>) -> String {
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".into(),
+        "VERSION:2.0".into(),
+        "PRODID:-//on_job//on_job//EN".into(),
+    ];
+    for task in tasks {
+        lines.push("BEGIN:VTODO".into());
+        if let Some(uuid) = task.fields.get("uuid") {
+            lines.push(format!("UID:{}", escape(uuid)));
+        }
+        lines.push(format!("DTSTAMP:{}", format_basic_date_time(now)));
+        if let Some(created) = task.created {
+            lines.push(format!("CREATED:{}", format_basic_date_time(created)));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&task.name)));
+        lines.extend(categories(task));
+        // DTSTART may not come after DUE, so a task scheduled past its deadline leaves it out
+        if let Some(scheduled) = task
+            .scheduled
+            .filter(|&scheduled| scheduled <= task.deadline)
+        {
+            lines.push(format!("DTSTART:{}", format_basic_date_time(scheduled)));
+        }
+        lines.push(format!("DUE:{}", format_basic_date_time(task.deadline)));
+        let status = match task.status {
+            Status::Todo | Status::Waiting => "NEEDS-ACTION",
+            Status::InProgress => "IN-PROCESS",
+            Status::Cancelled => "CANCELLED",
+            Status::Done => "COMPLETED",
+        };
+        lines.push(format!("STATUS:{status}"));
+        if let Some(completed) = task.completed {
+            lines.push(format!("COMPLETED:{}", format_basic_date_time(completed)));
+        }
+        if let Some(priority) = task.priority {
+            let priority = match priority {
+                Priority::High => 1,
+                Priority::Medium => 5,
+                Priority::Low => 9,
+            };
+            lines.push(format!("PRIORITY:{priority}"));
+        }
+        if !task.notes.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&task.notes.join("\n"))));
+        }
+        lines.push("END:VTODO".into());
+    }
+    if events {
+        for task in tasks.iter().filter(|task| task.is_open()) {
+            lines.push("BEGIN:VEVENT".into());
+            if let Some(uuid) = task.fields.get("uuid") {
+                lines.push(format!("UID:{}-due", escape(uuid)));
+            }
+            lines.push(format!("DTSTAMP:{}", format_basic_date_time(now)));
+            lines.push(format!("SUMMARY:{}", escape(&task.name)));
+            lines.extend(categories(task));
+            // The event ends at the deadline, leaving room for the work when it is estimated
+            let start = match task.estimate {
+                Some(Estimate::Time(estimate)) => task.deadline - estimate,
+                _ => task.deadline,
+            };
+            lines.push(format!("DTSTART:{}", format_basic_date_time(start)));
+            lines.push(format!("DTEND:{}", format_basic_date_time(task.deadline)));
+            lines.push("END:VEVENT".into());
+        }
+    }
+    lines.push("END:VCALENDAR".into());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Reads the VTODO entries of an iCalendar document, ignoring every other component. A VTODO
+/// that can't be read is rejected with the line it begins on, and the others are still imported
+pub(super) fn import<Tz: TimeZone>(input: &str, tz: &Tz) -> Imported {
+    let mut imported = Imported::default();
+    // The line a VTODO began on, and its properties so far or why it can't be read
+    let mut todo: Option<(usize, Result<Vec<Property>, ImportError>)> = None;
+    // How deep inside the VTODO we are in components of its own, like VALARM
+    let mut nested = 0;
+    for (line, text) in unfold(input) {
+        let property = match Property::parse(line, &text) {
+            Ok(property) => property,
+            Err(e) => {
+                match todo.as_mut() {
+                    Some((_, properties @ Ok(_))) => *properties = Err(e),
+                    Some(_) => {}
+                    None => imported.rejected.push(e),
+                }
+                continue;
+            }
+        };
+        match property.name.as_str() {
+            "BEGIN" if todo.is_none() => {
+                if property.value.eq_ignore_ascii_case("VTODO") {
+                    todo = Some((line, Ok(Vec::new())));
+                }
+            }
+            "BEGIN" => nested += 1,
+            "END" if todo.is_some() && nested > 0 => nested -= 1,
+            "END" => {
+                if let Some((begin, properties)) = todo.take() {
+                    match properties.and_then(|properties| task_from(begin, properties, tz)) {
+                        Ok(task) => imported.tasks.push(task),
+                        Err(e) => imported.rejected.push(e),
+                    }
+                }
+            }
+            _ => {
+                if let Some((_, Ok(properties))) = todo.as_mut().filter(|_| nested == 0) {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+    match todo {
+        Some((_, Err(e))) => imported.rejected.push(e),
+        Some((line, Ok(_))) => imported
+            .rejected
+            .push(ImportError::new(line, "VTODO is never closed")),
+        None => {}
+    }
+    imported
+}
+
+fn task_from<Tz: TimeZone>(
+    line: usize,
+    properties: Vec<Property>,
+    tz: &Tz,
+) -> Result<Task, ImportError> {
+    let mut task = Task::default();
+    let mut name = None;
+    let mut deadline = None;
+    let mut tags = Vec::new();
+    for property in properties {
+        let value = property.value.trim();
+        match property.name.as_str() {
+            // Kept like the UUIDs of other tools, so importing the same VTODO again adds nothing
+            "UID" => {
+                task.fields
+                    .insert("uuid".into(), storable(&unescape(value)));
+            }
+            "SUMMARY" => name = Some(storable(&unescape(value))),
+            "CATEGORIES" => tags.extend(
+                split_list(value)
+                    .iter()
+                    .map(|tag| storable(&tag.replace(',', " ")))
+                    .filter(|tag| !tag.is_empty()),
+            ),
+            "DUE" => deadline = Some(property.date_time(tz, end_of_day())?),
+            "DTSTART" => task.scheduled = Some(property.date_time(tz, NaiveTime::MIN)?),
+            "CREATED" => task.created = Some(property.date_time(tz, NaiveTime::MIN)?),
+            "COMPLETED" => task.completed = Some(property.date_time(tz, NaiveTime::MIN)?),
+            "STATUS" => {
+                task.status = match value.to_uppercase().as_str() {
+                    "NEEDS-ACTION" => Status::Todo,
+                    "IN-PROCESS" => Status::InProgress,
+                    "CANCELLED" => Status::Cancelled,
+                    "COMPLETED" => Status::Done,
+                    _ => return Err(property.invalid("status")),
+                }
+            }
+            "PRIORITY" => {
+                task.priority = match value.parse::<u8>() {
+                    Ok(0) => None,
+                    Ok(1..=4) => Some(Priority::High),
+                    Ok(5) => Some(Priority::Medium),
+                    Ok(6..=9) => Some(Priority::Low),
+                    _ => return Err(property.invalid("priority")),
+                }
+            }
+            "DESCRIPTION" => task.notes.extend(
+                unescape(value)
+                    .lines()
+                    .map(storable)
+                    .filter(|note| !note.is_empty()),
+            ),
+            _ => {}
+        }
+    }
+    task.name = name
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| ImportError::new(line, "VTODO has no SUMMARY"))?;
+    task.deadline = deadline.ok_or_else(|| ImportError::new(line, "VTODO has no DUE date"))?;
+    task.tags = (!tags.is_empty()).then_some(tags);
+    if task.is_open() {
+        task.completed = None;
+    }
+    Ok(task)
+}
+
+/// One `NAME;PARAM=VALUE:VALUE` content line, keeping only the name and value
+struct Property {
+    line: usize,
+    name: String,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: usize, text: &str) -> Result<Self, ImportError> {
+        // Parameter values may be quoted, and a quoted colon does not end the name
+        let mut quoted = false;
+        let colon = text.char_indices().find_map(|(idx, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(idx),
+            _ => None,
+        });
+        let colon = colon.ok_or_else(|| {
+            ImportError::new(line, format!("\"{text}\" is not of the form NAME:VALUE"))
+        })?;
+        let name = text[..colon].split(';').next().unwrap_or_default();
+        Ok(Property {
+            line,
+            name: name.trim().to_uppercase(),
+            value: text[colon + 1..].into(),
+        })
+    }
+
+    /// Reads a DATE or DATE-TIME value, placing dates at `time_of_day`
+    fn date_time<Tz: TimeZone>(
+        &self,
+        tz: &Tz,
+        time_of_day: NaiveTime,
+    ) -> Result<DateTime<Utc>, ImportError> {
+        let value = self.value.trim();
+        if let Some(utc) = value.strip_suffix('Z') {
+            return NaiveDateTime::parse_from_str(utc, BASIC_DATE_TIME)
+                .map(|date_time| date_time.and_utc())
+                .map_err(|_| self.invalid("date"));
+        }
+        let naive = match NaiveDateTime::parse_from_str(value, BASIC_DATE_TIME) {
+            Ok(date_time) => date_time,
+            Err(_) => NaiveDate::parse_from_str(value, "%Y%m%d")
+                .map_err(|_| self.invalid("date"))?
+                .and_time(time_of_day),
+        };
+        // Without a time zone database, times given in a TZID are read like floating times
+        tz.from_local_datetime(&naive)
+            .earliest()
+            .map(|date_time| date_time.to_utc())
+            .ok_or_else(|| self.invalid("date"))
+    }
+
+    fn invalid(&self, what: &str) -> ImportError {
+        ImportError::new(
+            self.line,
+            format!("\"{}\" is not a valid {what} for {}", self.value, self.name),
+        )
+    }
+}
+
+fn categories(task: &Task) -> Option<String> {
+    let tags = task.tags.as_deref().unwrap_or_default();
+    (!tags.is_empty()).then(|| {
+        let tags: Vec<String> = tags.iter().map(|tag| escape(tag)).collect();
+        format!("CATEGORIES:{}", tags.join(","))
+    })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    split_list(text).join(",")
+}
+
+/// Splits a value on the commas that are not escaped, unescaping each part
+fn split_list(text: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().expect("Never empty");
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => part.push('\n'),
+                Some(escaped) => part.push(escaped),
+                None => {}
+            },
+            ',' => parts.push(String::new()),
+            _ => part.push(c),
+        }
+    }
+    parts
+}
+
+/// Breaks lines longer than 75 bytes, continuing them on lines starting with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Joins folded lines back together, numbering each by the line it starts on
+fn unfold(input: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some((_, previous))) => previous.push_str(continuation),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push((idx + 1, line.into())),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::exchange::test_util::date_time;
+    use chrono::TimeDelta;
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            Task {
+                name: "Review budget, then reply; quickly".into(),
+                tags: Some(vec!["finance".into(), "q2".into()]),
+                deadline: date_time("2025-03-14T17:00:00+00:00"),
+                uid: Some(1),
+                fields: [("uuid".into(), "5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e01".into())].into(),
+                priority: Some(Priority::High),
+                created: Some(date_time("2025-03-01T09:30:00+00:00")),
+                scheduled: Some(date_time("2025-03-12T09:00:00+00:00")),
+                estimate: Some(Estimate::Time(TimeDelta::minutes(90))),
+                notes: vec!["Ask Bob first".into(), "Numbers in the shared sheet".into()],
+                ..Default::default()
+            },
+            Task {
+                name: "Renew the parking permit before the office moves to the new building".into(),
+                deadline: date_time("2025-03-10T17:00:00+00:00"),
+                status: Status::Done,
+                uid: Some(2),
+                fields: [("uuid".into(), "5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e02".into())].into(),
+                completed: Some(date_time("2025-03-09T12:00:00+00:00")),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn export_tasks_as_vtodos() {
+        let exported = export(&tasks(), true, date_time("2025-03-10T09:00:00+00:00"));
+        let lines: Vec<&str> = exported.split_terminator('\n').collect();
+        assert!(
+            lines
+                .iter()
+                .all(|line| line.ends_with('\r') && line.len() <= 76)
+        );
+        assert_eq!(
+            exported.replace("\r\n", "\n"),
+            include_str!("fixtures/export.ics")
+        );
+    }
+
+    #[test]
+    fn import_vtodos_round_trip() {
+        let exported = export(&tasks(), false, date_time("2025-03-10T09:00:00+00:00"));
+        let mut expected = tasks();
+        // Estimates have no place in a VTODO, and tasks are told apart by UID rather than uid
+        expected[0].estimate = None;
+        for task in &mut expected {
+            task.uid = None;
+        }
+        let imported = import(&exported, &Utc);
+        assert!(imported.rejected.is_empty());
+        assert_eq!(imported.tasks, expected);
+    }
+
+    #[test]
+    fn import_vtodos_from_calendar_app() {
+        let tasks = import(include_str!("fixtures/import.ics"), &Utc).tasks;
+        let expected = vec![
+            Task {
+                name: "Book flights to Lisbon for the offsite in May, aisle seat".into(),
+                tags: Some(vec!["travel".into(), "offsite".into()]),
+                deadline: date_time("2025-04-02T17:00:00+00:00"),
+                status: Status::InProgress,
+                priority: Some(Priority::Low),
+                created: Some(date_time("2025-03-01T08:00:00+00:00")),
+                notes: vec!["Budget: 400 / person".into()],
+                fields: [("uuid".into(), "5f6c2a1e-93b4-4d0e-b1a7-2a9c1f0e8d11".into())].into(),
+                ..Default::default()
+            },
+            Task {
+                name: "Send invoice".into(),
+                deadline: date_time("2025-03-31T12:00:00+00:00"),
+                status: Status::Cancelled,
+                fields: [("uuid".into(), "invoice-2025-03@example.com".into())].into(),
+                completed: Some(date_time("2025-03-20T10:00:00+00:00")),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(tasks, expected);
+    }
+
+    #[test]
+    fn import_vtodo_errors() {
+        let input = "BEGIN:VCALENDAR\nBEGIN:VTODO\nSUMMARY:No due date\nEND:VTODO\n\
+            BEGIN:VTODO\nSUMMARY:Due\nDUE:20250310\nEND:VTODO\nEND:VCALENDAR\n";
+        let imported = import(input, &Utc);
+        assert_eq!(
+            imported.rejected,
+            vec![ImportError::new(2, "VTODO has no DUE date")]
+        );
+        assert_eq!(imported.tasks.len(), 1);
+        assert_eq!(imported.tasks[0].name, "Due");
+        assert_eq!(
+            imported.tasks[0].deadline,
+            date_time("2025-03-10T17:00:00+00:00")
+        );
+        let input = "BEGIN:VTODO\nSUMMARY:Bad\nDUE:2025-03-10\nEND:VTODO\n";
+        assert_eq!(import(input, &Utc).rejected[0].line, 3);
+        let input = "BEGIN:VTODO\nSUMMARY Bad\n";
+        assert_eq!(import(input, &Utc).rejected[0].line, 2);
+        let input = "BEGIN:VTODO\nSUMMARY:Unclosed\n";
+        assert_eq!(
+            import(input, &Utc).rejected,
+            vec![ImportError::new(1, "VTODO is never closed")]
+        );
+    }
+}
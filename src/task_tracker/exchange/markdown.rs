@@ -0,0 +1,285 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::collections::BTreeMap;
+
+use crate::task_tracker::exchange::{ImportError, Imported, end_of_today, storable};
+use crate::task_tracker::report::UNTAGGED;
+use crate::task_tracker::task::{Status, Task, end_of_day};
+
+/// Writes a GitHub-flavored Markdown task list under a heading for each task's first tag, with
+/// the untagged tasks last. Every item keeps all its tags as `#tag` and its deadline as
+/// `@due(...)`, so pasted elsewhere and imported again it loses nothing of either
+pub(super) fn export<Tz: TimeZone>(tasks: &[Task], tz: &Tz) -> String {
+    let mut groups: BTreeMap<Option<&str>, Vec<&Task>> = BTreeMap::new();
+    for task in tasks {
+        let tag = task.tags.as_ref().and_then(|tags| tags.first());
+        groups
+            .entry(tag.map(String::as_str))
+            .or_default()
+            .push(task);
+    }
+    // `None` sorts first, but untagged tasks read best after the tagged ones
+    let untagged = groups.remove(&None);
+    let sections = groups
+        .into_iter()
+        .map(|(tag, tasks)| (tag.unwrap_or_default(), tasks))
+        .chain(untagged.map(|tasks| (UNTAGGED, tasks)));
+
+    let mut lines = Vec::new();
+    for (heading, tasks) in sections {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("## {heading}"));
+        lines.push(String::new());
+        lines.extend(tasks.into_iter().map(|task| item(task, tz)));
+    }
+    lines.into_iter().map(|line| line + "\n").collect()
+}
+
+fn item<Tz: TimeZone>(task: &Task, tz: &Tz) -> String {
+    let mut words = vec![match task.status {
+        Status::Done => "- [x]".to_string(),
+        // Struck through, so cancelled tasks don't read as done
+        Status::Cancelled => format!("- [x] ~~{}~~", task.name),
+        _ => "- [ ]".to_string(),
+    }];
+    if task.status != Status::Cancelled {
+        words.push(task.name.clone());
+    }
+    for tag in task.tags.iter().flatten() {
+        words.push(format!(
+            "#{}",
+            tag.split_whitespace().collect::<Vec<_>>().join("_")
+        ));
+    }
+    let deadline = task.deadline.with_timezone(tz).naive_local();
+    words.push(match deadline.time() == end_of_day() {
+        true => format!("@due({})", deadline.format("%Y-%m-%d")),
+        false => format!("@due({})", deadline.format("%Y-%m-%d %H:%M")),
+    });
+    words.join(" ")
+}
+
+/// Reads every task list item, checked or not, ignoring the rest of the document. `#tag` words
+/// become tags, except numbers like `#123` which are issue references, and `@due(2025-03-10)`
+/// or `@due(2025-03-10 09:30)` sets the deadline, which is otherwise 17:00 on the day of `now`
+pub(super) fn import<Tz: TimeZone>(input: &str, tz: &Tz, now: DateTime<Utc>) -> Imported {
+    let default_deadline = end_of_today(tz, now);
+    let mut imported = Imported::default();
+    for (idx, line) in input.lines().enumerate() {
+        let Some((checked, text)) = task_list_item(line) else {
+            continue;
+        };
+        match from_item(idx + 1, checked, text, tz, default_deadline) {
+            Ok(task) => imported.tasks.push(task),
+            Err(e) => imported.rejected.push(e),
+        }
+    }
+    imported
+}
+
+/// Whether a `- [x] text` line is checked, and its text, for any list marker and indentation
+fn task_list_item(line: &str) -> Option<(bool, &str)> {
+    let line = line.trim_start();
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = line.find(|c: char| !c.is_ascii_digit())?;
+            line[digits..]
+                .strip_prefix(['.', ')'])
+                .filter(|_| digits > 0)?
+        }
+    };
+    let rest = rest.strip_prefix(' ')?.trim_start();
+    let checked = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let text = &rest[3..];
+    (text.is_empty() || text.starts_with(char::is_whitespace)).then_some((checked, text.trim()))
+}
+
+fn from_item<Tz: TimeZone>(
+    line: usize,
+    checked: bool,
+    text: &str,
+    tz: &Tz,
+    default_deadline: DateTime<Utc>,
+) -> Result<Task, ImportError> {
+    let mut task = Task {
+        deadline: default_deadline,
+        status: if checked { Status::Done } else { Status::Todo },
+        ..Default::default()
+    };
+    let mut name = Vec::new();
+    let mut tags = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("@due(") {
+        let end = rest[start..]
+            .find(')')
+            .ok_or_else(|| ImportError::new(line, "@due( is never closed"))?;
+        let due = rest[start + 5..start + end].trim();
+        task.deadline = parse_due(due, tz)
+            .ok_or_else(|| ImportError::new(line, format!("\"{due}\" is not a valid due date")))?;
+        name.push(&rest[..start]);
+        rest = &rest[start + end + 1..];
+    }
+    name.push(rest);
+
+    let mut words = Vec::new();
+    for word in name.iter().flat_map(|part| part.split_whitespace()) {
+        match word.strip_prefix('#') {
+            Some(tag) if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) => {
+                tags.push(storable(&tag.replace(',', " ")))
+            }
+            _ => words.push(word),
+        }
+    }
+    let mut name = words.join(" ");
+    if let Some(struck) = name
+        .strip_prefix("~~")
+        .and_then(|name| name.strip_suffix("~~"))
+    {
+        if checked {
+            task.status = Status::Cancelled;
+        }
+        name = struck.trim().into();
+    }
+    task.name = storable(&name);
+    task.tags = (!tags.is_empty()).then_some(tags);
+    match task.name.is_empty() {
+        true => Err(ImportError::new(line, "a task needs a name")),
+        false => Ok(task),
+    }
+}
+
+fn parse_due<Tz: TimeZone>(due: &str, tz: &Tz) -> Option<DateTime<Utc>> {
+    let naive = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(due, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(due, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(end_of_day()))
+        })?;
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|deadline| deadline.to_utc())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::exchange::test_util::{date_time, tz};
+
+    #[test]
+    fn export_groups_by_tag() {
+        let task = |name: &str, tags: &[&str], deadline: &str, status: Status| Task {
+            name: name.into(),
+            tags: (!tags.is_empty()).then(|| tags.iter().map(|tag| tag.to_string()).collect()),
+            deadline: date_time(deadline),
+            status,
+            ..Default::default()
+        };
+        let tasks = [
+            task("Water plants", &[], "2025-03-10T16:00:00Z", Status::Todo),
+            task(
+                "Write report",
+                &["work", "q1"],
+                "2025-03-11T08:30:00Z",
+                Status::Done,
+            ),
+            task(
+                "Fix the roof",
+                &["home"],
+                "2025-03-12T16:00:00Z",
+                Status::Cancelled,
+            ),
+            task(
+                "Review PR",
+                &["work"],
+                "2025-03-13T16:00:00Z",
+                Status::InProgress,
+            ),
+        ];
+        assert_eq!(
+            export(&tasks, &tz()),
+            "## home\n\
+             \n\
+             - [x] ~~Fix the roof~~ #home @due(2025-03-12)\n\
+             \n\
+             ## work\n\
+             \n\
+             - [x] Write report #work #q1 @due(2025-03-11 09:30)\n\
+             - [ ] Review PR #work @due(2025-03-13)\n\
+             \n\
+             ## (untagged)\n\
+             \n\
+             - [ ] Water plants @due(2025-03-10)\n"
+        );
+        let imported = import(
+            &export(&tasks, &tz()),
+            &tz(),
+            date_time("2025-03-01T00:00:00Z"),
+        );
+        assert_eq!(imported.tasks.len(), 4);
+        assert_eq!(imported.tasks[0].status, Status::Cancelled);
+        assert_eq!(imported.tasks[1].tags, tasks[1].tags);
+        assert_eq!(imported.tasks[1].deadline, tasks[1].deadline);
+    }
+
+    #[test]
+    fn import_meeting_notes() {
+        let notes = "# Planning, 2025-03-07\n\
+                     \n\
+                     Decided to ship on Friday. See #123.\n\
+                     \n\
+                     - [ ] Draft the release notes #docs @due(2025-03-10)\n\
+                     - [x] Book the room\n\
+                     * [X] Fix #123 #bug @due(2025-03-08 09:30) before the demo\n\
+                     \x20 1. [ ] Email Acme, Inc. #sales #work\n\
+                     - [] Not a task\n\
+                     - [ ] @due(2025-03-11)\n\
+                     - [ ] Pay rent @due(next week)\n\
+                     - a plain list item\n";
+        let imported = import(notes, &tz(), date_time("2025-03-07T12:00:00Z"));
+        assert_eq!(
+            imported.tasks,
+            vec![
+                Task {
+                    name: "Draft the release notes".into(),
+                    tags: Some(vec!["docs".into()]),
+                    deadline: date_time("2025-03-10T16:00:00Z"),
+                    ..Default::default()
+                },
+                Task {
+                    name: "Book the room".into(),
+                    deadline: date_time("2025-03-07T16:00:00Z"),
+                    status: Status::Done,
+                    ..Default::default()
+                },
+                Task {
+                    name: "Fix #123 before the demo".into(),
+                    tags: Some(vec!["bug".into()]),
+                    deadline: date_time("2025-03-08T08:30:00Z"),
+                    status: Status::Done,
+                    ..Default::default()
+                },
+                Task {
+                    name: "Email Acme, Inc.".into(),
+                    tags: Some(vec!["sales".into(), "work".into()]),
+                    deadline: date_time("2025-03-07T16:00:00Z"),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(
+            imported.rejected,
+            vec![
+                ImportError::new(10, "a task needs a name"),
+                ImportError::new(11, "\"next week\" is not a valid due date"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, TimeZone, Utc};
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::task_tracker::task::{Task, end_of_day};
+
+pub mod csv;
+pub mod ics;
+pub mod markdown;
+pub mod org;
+pub mod taskwarrior;
+
+/// A format other tools read and write tasks in, for `export` and `import`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExchangeFormat {
+    /// iCalendar, with tasks as VTODO entries
+    Ics,
+    /// The JSON of Taskwarrior's `task export` and `task import`
+    Taskwarrior,
+    /// Comma-separated values with a header row, as spreadsheets read and write them
+    Csv,
+    /// A GitHub-flavored Markdown task list, with `#tag` and `@due(...)` annotations
+    Markdown,
+    /// Emacs Org-mode headings with TODO keywords
+    Org,
+}
+
+impl Display for ExchangeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ExchangeFormat::Ics => "ics".fmt(f),
+            ExchangeFormat::Taskwarrior => "taskwarrior".fmt(f),
+            ExchangeFormat::Csv => "csv".fmt(f),
+            ExchangeFormat::Markdown => "markdown".fmt(f),
+            ExchangeFormat::Org => "org".fmt(f),
+        }
+    }
+}
+
+impl FromStr for ExchangeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ics" | "ical" | "icalendar" => Ok(ExchangeFormat::Ics),
+            "taskwarrior" | "tw" => Ok(ExchangeFormat::Taskwarrior),
+            "csv" => Ok(ExchangeFormat::Csv),
+            "markdown" | "md" => Ok(ExchangeFormat::Markdown),
+            "org" => Ok(ExchangeFormat::Org),
+            _ => Err(format!(
+                "\"{s}\" is not one of ics, taskwarrior, csv, markdown or org"
+            )),
+        }
+    }
+}
+
+/// Settings for `export`, which formats without a use for them ignore
+#[derive(Debug, Default)]
+pub struct ExportOptions {
+    /// Also write a calendar event at the deadline of every open task
+    pub events: bool,
+    /// Columns of a CSV export, the default ones when empty
+    pub columns: Vec<String>,
+}
+
+/// Settings for `import`, which formats without a use for them ignore
+#[derive(Debug, Default)]
+pub struct ImportOptions {
+    /// Renames CSV headers to the columns they hold, or drops them when renamed to nothing
+    pub mapping: Vec<(String, String)>,
+}
+
+/// The tasks an import could read, and why the others could not be
+#[derive(Debug, Default)]
+pub(super) struct Imported {
+    pub tasks: Vec<Task>,
+    pub rejected: Vec<ImportError>,
+}
+
+/// Writes `tasks` out in `format`, with local times in `tz`
+pub(super) fn export<Tz: TimeZone>(
+    format: ExchangeFormat,
+    tasks: &[Task],
+    options: &ExportOptions,
+    tz: &Tz,
+    now: DateTime<Utc>,
+) -> String {
+    match format {
+        ExchangeFormat::Ics => ics::export(tasks, options.events, now),
+        ExchangeFormat::Taskwarrior => taskwarrior::export(tasks, now),
+        ExchangeFormat::Csv => csv::export(tasks, &options.columns, tz),
+        ExchangeFormat::Markdown => markdown::export(tasks, tz),
+        ExchangeFormat::Org => org::export(tasks, tz),
+    }
+}
+
+/// Reads tasks written in `format`, taking times without an offset to be in `tz`
+pub(super) fn import<Tz: TimeZone>(
+    format: ExchangeFormat,
+    input: &str,
+    options: &ImportOptions,
+    tz: &Tz,
+    now: DateTime<Utc>,
+) -> Result<Imported, ImportError> {
+    match format {
+        ExchangeFormat::Ics => Ok(ics::import(input, tz)),
+        ExchangeFormat::Taskwarrior => taskwarrior::import(input, tz, now),
+        ExchangeFormat::Csv => csv::import(input, &options.mapping, tz, now),
+        ExchangeFormat::Markdown => Ok(markdown::import(input, tz, now)),
+        ExchangeFormat::Org => Ok(org::import(input, tz, now)),
+    }
+}
+
+/// The compact form of ISO 8601 both iCalendar and Taskwarrior write times in, before the `Z`
+const BASIC_DATE_TIME: &str = "%Y%m%dT%H%M%S";
+
+fn format_basic_date_time(date_time: DateTime<Utc>) -> String {
+    format!("{}Z", date_time.format(BASIC_DATE_TIME))
+}
+
+/// The deadline of tasks imported without one: 17:00 on the day of `now` in `tz`
+fn end_of_today<Tz: TimeZone>(tz: &Tz, now: DateTime<Utc>) -> DateTime<Utc> {
+    tz.from_local_datetime(&now.with_timezone(tz).date_naive().and_time(end_of_day()))
+        .earliest()
+        .map_or(now, |deadline| deadline.to_utc())
+}
+
+/// Text as it can be kept in the task file, which gives `|` and line breaks a meaning of their own
+fn storable(text: &str) -> String {
+    text.replace('|', "/")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Helpers shared by the tests of the task file formats and the formats tasks are exchanged in
+#[cfg(test)]
+pub(super) mod test_util {
+    use chrono::{DateTime, FixedOffset, Utc};
+
+    pub fn date_time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    /// A time zone an hour ahead of UTC, to tell local times from UTC ones
+    pub fn tz() -> FixedOffset {
+        FixedOffset::east_opt(3600).unwrap()
+    }
+}
+
+/// Why a line of imported input could not be read
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl ImportError {
+    fn new(line: usize, reason: impl Into<String>) -> Self {
+        ImportError {
+            line,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl Error for ImportError {}
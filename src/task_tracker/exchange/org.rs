@@ -0,0 +1,364 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use crate::task_tracker::exchange::{ImportError, Imported, end_of_today, storable};
+use crate::task_tracker::task::{Priority, Status, Task, end_of_day};
+
+/// Declares the states beyond TODO and DONE, so Org knows which ones are closed
+const TODO_KEYWORDS: &str = "#+TODO: TODO STARTED WAITING | DONE CANCELLED";
+/// Org's own property for an entry's globally unique id, which keeps a task's UUID so tasks
+/// exported from the task file are recognized on import
+const ID_PROPERTY: &str = "ID";
+
+/// Writes a heading per task, `* TODO [#A] name :tag1:tag2:`, followed by its deadline, schedule
+/// and closing time, its UUID in a property drawer and its notes. Org timestamps have no time
+/// zone, so times are written in `tz`, and deadlines at 17:00 are written as a day only
+pub(super) fn export<Tz: TimeZone>(tasks: &[Task], tz: &Tz) -> String {
+    let mut lines = vec![TODO_KEYWORDS.to_string(), String::new()];
+    for task in tasks {
+        lines.push(heading(task));
+        let mut planning = Vec::new();
+        if let (false, Some(completed)) = (task.status.is_open(), task.completed) {
+            planning.push(format!("CLOSED: [{}]", timestamp(completed, tz, false)));
+        }
+        planning.push(format!(
+            "DEADLINE: <{}>",
+            timestamp(task.deadline, tz, true)
+        ));
+        if let Some(scheduled) = task.scheduled {
+            planning.push(format!("SCHEDULED: <{}>", timestamp(scheduled, tz, false)));
+        }
+        lines.push(planning.join(" "));
+        if let Some(uuid) = task.fields.get("uuid") {
+            lines.push(":PROPERTIES:".into());
+            lines.push(format!(":{ID_PROPERTY}: {uuid}"));
+            lines.push(":END:".into());
+        }
+        lines.extend(task.notes.iter().cloned());
+    }
+    lines.into_iter().map(|line| line + "\n").collect()
+}
+
+fn heading(task: &Task) -> String {
+    let keyword = match task.status {
+        Status::Todo => "TODO",
+        Status::InProgress => "STARTED",
+        Status::Waiting => "WAITING",
+        Status::Done => "DONE",
+        Status::Cancelled => "CANCELLED",
+    };
+    let mut words = vec!["*", keyword];
+    words.extend(task.priority.map(|priority| match priority {
+        Priority::High => "[#A]",
+        Priority::Medium => "[#B]",
+        Priority::Low => "[#C]",
+    }));
+    words.push(&task.name);
+    let tags: Vec<String> = task
+        .tags
+        .iter()
+        .flatten()
+        // Org tags are made of letters, digits, `_`, `@`, `#` and `%`
+        .map(|tag| tag.replace(|c: char| !c.is_alphanumeric() && !"_@#%".contains(c), "_"))
+        .collect();
+    let tags = format!(":{}:", tags.join(":"));
+    if tags != "::" {
+        words.push(&tags);
+    }
+    words.join(" ")
+}
+
+/// An Org timestamp like `2025-03-12 Wed 09:30`, leaving out the time of deadlines at 17:00
+fn timestamp<Tz: TimeZone>(date_time: DateTime<Utc>, tz: &Tz, deadline: bool) -> String {
+    let local = date_time.with_timezone(tz).naive_local();
+    match deadline && local.time() == end_of_day() {
+        true => local.format("%Y-%m-%d %a").to_string(),
+        false => local.format("%Y-%m-%d %a %H:%M").to_string(),
+    }
+}
+
+/// Reads every heading with a TODO keyword as a task, ignoring other headings and text outside
+/// them. Tags, priority cookies, DEADLINE, SCHEDULED and CLOSED map onto the task and the text
+/// under the heading becomes notes. Timestamps are local times in `tz`; a deadline without a
+/// time is due at 17:00, and a task without one is due at 17:00 on the day of `now`
+pub(super) fn import<Tz: TimeZone>(input: &str, tz: &Tz, now: DateTime<Utc>) -> Imported {
+    let default_deadline = end_of_today(tz, now);
+    let mut imported = Imported::default();
+    // The task under construction, or the error it ran into, until the next heading
+    let mut current: Option<Result<Task, ImportError>> = None;
+    let mut in_drawer = false;
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        if let Some(heading) = heading_text(line) {
+            match current.take() {
+                Some(Ok(task)) => imported.tasks.push(task),
+                Some(Err(e)) => imported.rejected.push(e),
+                None => {}
+            }
+            current = from_heading(line_number, heading, default_deadline);
+            in_drawer = false;
+            continue;
+        }
+        let Some(Ok(task)) = current.as_mut() else {
+            continue;
+        };
+        let trimmed = line.trim();
+        if in_drawer {
+            in_drawer = !trimmed.eq_ignore_ascii_case(":END:");
+            if let Some(uuid) = trimmed
+                .strip_prefix(&format!(":{ID_PROPERTY}:"))
+                .map(str::trim)
+                .filter(|uuid| !uuid.is_empty())
+            {
+                task.fields.insert("uuid".into(), uuid.into());
+            }
+        } else if trimmed.starts_with(':') && trimmed.ends_with(':') && trimmed.len() > 1 {
+            // Drawers like :PROPERTIES: or :LOGBOOK: hold bookkeeping rather than notes
+            in_drawer = true;
+        } else if ["DEADLINE:", "SCHEDULED:", "CLOSED:"]
+            .iter()
+            .any(|keyword| trimmed.starts_with(keyword))
+        {
+            if let Err(e) = planning(line_number, trimmed, task, tz) {
+                current = Some(Err(e));
+            }
+        } else if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            task.notes.push(storable(trimmed));
+        }
+    }
+    match current {
+        Some(Ok(task)) => imported.tasks.push(task),
+        Some(Err(e)) => imported.rejected.push(e),
+        None => {}
+    }
+    imported
+}
+
+/// The text of a heading line, after its stars
+fn heading_text(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('*');
+    (text.len() < line.len() && text.starts_with(' ')).then(|| text.trim())
+}
+
+fn from_heading(
+    line: usize,
+    heading: &str,
+    default_deadline: DateTime<Utc>,
+) -> Option<Result<Task, ImportError>> {
+    let (keyword, rest) = heading.split_once(' ').unwrap_or((heading, ""));
+    let status = match keyword {
+        "TODO" | "NEXT" => Status::Todo,
+        "STARTED" => Status::InProgress,
+        "WAITING" => Status::Waiting,
+        "DONE" => Status::Done,
+        "CANCELLED" | "CANCELED" => Status::Cancelled,
+        _ => return None,
+    };
+    let mut task = Task {
+        deadline: default_deadline,
+        status,
+        ..Default::default()
+    };
+    let mut rest = rest.trim();
+    for (cookie, priority) in [
+        ("[#A]", Priority::High),
+        ("[#B]", Priority::Medium),
+        ("[#C]", Priority::Low),
+    ] {
+        if let Some(after) = rest.strip_prefix(cookie) {
+            task.priority = Some(priority);
+            rest = after.trim_start();
+        }
+    }
+    let (title, tags) = rest.rsplit_once(char::is_whitespace).unwrap_or(("", rest));
+    if tags.len() > 2 && tags.starts_with(':') && tags.ends_with(':') {
+        let tags: Vec<String> = tags
+            .split(':')
+            .filter(|tag| !tag.is_empty())
+            .map(storable)
+            .collect();
+        task.tags = Some(tags);
+        rest = title.trim_end();
+    }
+    task.name = storable(rest);
+    Some(match task.name.is_empty() {
+        true => Err(ImportError::new(line, "a task needs a name")),
+        false => Ok(task),
+    })
+}
+
+/// Reads a planning line like `CLOSED: [2025-03-11 Tue 09:30] DEADLINE: <2025-03-12 Wed>`
+fn planning<Tz: TimeZone>(
+    line: usize,
+    text: &str,
+    task: &mut Task,
+    tz: &Tz,
+) -> Result<(), ImportError> {
+    let mut rest = text;
+    while let Some((keyword, after)) = rest.split_once(':') {
+        let after = after.trim_start();
+        let close = match after.chars().next() {
+            Some('<') => '>',
+            Some('[') => ']',
+            _ => {
+                return Err(ImportError::new(
+                    line,
+                    format!("{keyword} has no timestamp"),
+                ));
+            }
+        };
+        let end = after.find(close).ok_or_else(|| {
+            ImportError::new(line, format!("{keyword} timestamp is never closed"))
+        })?;
+        let stamp = &after[1..end];
+        let invalid = || ImportError::new(line, format!("\"{stamp}\" is not a valid timestamp"));
+        match keyword.trim() {
+            "DEADLINE" => {
+                task.deadline = parse_timestamp(stamp, end_of_day(), tz).ok_or_else(invalid)?
+            }
+            "SCHEDULED" => {
+                task.scheduled =
+                    Some(parse_timestamp(stamp, NaiveTime::MIN, tz).ok_or_else(invalid)?)
+            }
+            "CLOSED" => {
+                task.completed =
+                    Some(parse_timestamp(stamp, NaiveTime::MIN, tz).ok_or_else(invalid)?)
+            }
+            _ => {}
+        }
+        rest = &after[end + 1..];
+    }
+    Ok(())
+}
+
+/// Reads `2025-03-12 Wed 09:30`, where the day name, the time and anything after like a
+/// repeater are optional; a timestamp without a time is taken to be at `time`
+fn parse_timestamp<Tz: TimeZone>(stamp: &str, time: NaiveTime, tz: &Tz) -> Option<DateTime<Utc>> {
+    let mut words = stamp.split_whitespace();
+    let date = NaiveDate::parse_from_str(words.next()?, "%Y-%m-%d").ok()?;
+    // A time range like 09:30-10:00 starts at its first time
+    let time = words
+        .filter_map(|word| word.split('-').next())
+        .find_map(|word| NaiveTime::parse_from_str(word, "%H:%M").ok())
+        .unwrap_or(time);
+    tz.from_local_datetime(&NaiveDateTime::new(date, time))
+        .earliest()
+        .map(|date_time| date_time.to_utc())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::exchange::test_util::{date_time, tz};
+    use chrono::FixedOffset;
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            Task {
+                name: "Write report".into(),
+                tags: Some(vec!["work".into(), "q1".into()]),
+                deadline: date_time("2025-03-12T16:00:00Z"),
+                fields: [("uuid".into(), "5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e03".into())].into(),
+                priority: Some(Priority::High),
+                scheduled: Some(date_time("2025-03-10T08:00:00Z")),
+                notes: vec!["Ask Sam for the numbers".into()],
+                ..Default::default()
+            },
+            Task {
+                name: "Ship it".into(),
+                tags: Some(vec!["work".into()]),
+                deadline: date_time("2025-03-11T08:30:00Z"),
+                status: Status::Done,
+                completed: Some(date_time("2025-03-11T07:45:00Z")),
+                ..Default::default()
+            },
+            Task {
+                name: "Fix the roof".into(),
+                deadline: date_time("2025-03-14T16:00:00Z"),
+                status: Status::Waiting,
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn export_org_headings() {
+        assert_eq!(
+            export(&tasks(), &tz()),
+            "#+TODO: TODO STARTED WAITING | DONE CANCELLED\n\
+             \n\
+             * TODO [#A] Write report :work:q1:\n\
+             DEADLINE: <2025-03-12 Wed> SCHEDULED: <2025-03-10 Mon 09:00>\n\
+             :PROPERTIES:\n\
+             :ID: 5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e03\n\
+             :END:\n\
+             Ask Sam for the numbers\n\
+             * DONE Ship it :work:\n\
+             CLOSED: [2025-03-11 Tue 08:45] DEADLINE: <2025-03-11 Tue 09:30>\n\
+             * WAITING Fix the roof\n\
+             DEADLINE: <2025-03-14 Fri>\n"
+        );
+    }
+
+    #[test]
+    fn import_round_trips_an_export() {
+        let imported = import(
+            &export(&tasks(), &tz()),
+            &tz(),
+            date_time("2025-03-01T00:00:00Z"),
+        );
+        assert_eq!(imported.tasks, tasks());
+        assert!(imported.rejected.is_empty());
+    }
+
+    #[test]
+    fn import_org_file() {
+        let org = "#+TITLE: Today\n\
+                   * Projects\n\
+                   ** NEXT Call Acme, again :sales:@phone:\n\
+                   \x20  DEADLINE: <2025-03-12 Wed 10:00 +1w>\n\
+                   \x20  :LOGBOOK:\n\
+                   \x20  CLOCK: [2025-03-10 Mon 09:00]--[2025-03-10 Mon 09:30] =>  0:30\n\
+                   \x20  :END:\n\
+                   \x20  They prefer mornings | before 11\n\
+                   ** CANCELED [#C] Old idea\n\
+                   ** TODO Broken deadline\n\
+                   DEADLINE: <soon>\n\
+                   ** DONE :nothing:\n\
+                   *bold text, not a heading*\n\
+                   * TODO Plain\n";
+        // Timestamps are local times, here four hours behind UTC
+        let new_york = FixedOffset::west_opt(4 * 3600).unwrap();
+        let imported = import(org, &new_york, date_time("2025-03-07T12:00:00Z"));
+        assert_eq!(
+            imported.tasks,
+            vec![
+                Task {
+                    name: "Call Acme, again".into(),
+                    tags: Some(vec!["sales".into(), "@phone".into()]),
+                    deadline: date_time("2025-03-12T14:00:00Z"),
+                    notes: vec!["They prefer mornings / before 11".into()],
+                    ..Default::default()
+                },
+                Task {
+                    name: "Old idea".into(),
+                    deadline: date_time("2025-03-07T21:00:00Z"),
+                    status: Status::Cancelled,
+                    priority: Some(Priority::Low),
+                    ..Default::default()
+                },
+                Task {
+                    name: "Plain".into(),
+                    deadline: date_time("2025-03-07T21:00:00Z"),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(
+            imported.rejected,
+            vec![
+                ImportError::new(11, "\"soon\" is not a valid timestamp"),
+                ImportError::new(12, "a task needs a name"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,524 @@
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde_json::{Deserializer, Map, Value};
+use std::collections::HashMap;
+
+use crate::task_tracker::exchange::{
+    BASIC_DATE_TIME, ImportError, Imported, end_of_today, format_basic_date_time, storable,
+};
+use crate::task_tracker::task::{Priority, Status, Task};
+
+/// Attributes Taskwarrior only computes or numbers for itself, which mean nothing elsewhere
+const IGNORED: [&str; 3] = ["id", "urgency", "modified"];
+
+/// Holds the UUID of the task a subtask belongs to, as Taskwarrior's own `parent` is the
+/// template a recurring task was spawned from
+const SUBTASK_OF: &str = "subtask_of";
+
+/// Writes tasks the way `task export` does: a JSON array with one task per line.
+/// What Taskwarrior has no attribute for is kept in attributes of its own, which it preserves as
+/// orphaned UDAs, and links and checklist items become annotations. Parents and dependencies
+/// are given by UUID, so only those among the exported tasks are kept
+pub(super) fn export(tasks: &[Task], now: DateTime<Utc>) -> String {
+    let uuids: HashMap<usize, &str> = tasks
+        .iter()
+        .filter_map(|task| Some((task.uid?, task.fields.get("uuid")?.as_str())))
+        .collect();
+    let lines: Vec<String> = tasks
+        .iter()
+        .map(|task| Value::Object(to_taskwarrior(task, &uuids, now)).to_string())
+        .collect();
+    format!("[\n{}\n]\n", lines.join(",\n"))
+}
+
+fn to_taskwarrior(
+    task: &Task,
+    uuids: &HashMap<usize, &str>,
+    now: DateTime<Utc>,
+) -> Map<String, Value> {
+    let date = |date_time: DateTime<Utc>| Value::from(format_basic_date_time(date_time));
+    let mut object = Map::new();
+    // Custom fields go first so none of them can overwrite a real attribute. Among them is the
+    // UUID every exported task has, which tasks imported from Taskwarrior keep from there
+    for (name, value) in &task.fields {
+        object.insert(name.clone(), value.clone().into());
+    }
+    object.insert("description".into(), task.name.clone().into());
+    let status = match task.status {
+        Status::Todo | Status::InProgress | Status::Waiting => "pending",
+        Status::Cancelled => "deleted",
+        Status::Done => "completed",
+    };
+    object.insert("status".into(), status.into());
+    let entry = task.created.unwrap_or(now);
+    object.insert("entry".into(), date(entry));
+    object.insert("due".into(), date(task.deadline));
+    if let Some(completed) = task.completed {
+        object.insert("end".into(), date(completed));
+    }
+    // Taskwarrior calls tasks active once started, which is what in progress means here
+    if task.status == Status::InProgress {
+        let start = task
+            .work_log
+            .last()
+            .map_or(entry, |interval| interval.start);
+        object.insert("start".into(), date(start));
+    }
+    if let Some(tags) = task.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        object.insert("tags".into(), tags.clone().into());
+    }
+    if let Some(priority) = task.priority {
+        let priority = match priority {
+            Priority::High => "H",
+            Priority::Medium => "M",
+            Priority::Low => "L",
+        };
+        object.insert("priority".into(), priority.into());
+    }
+    if let Some(scheduled) = task.scheduled {
+        object.insert("scheduled".into(), date(scheduled));
+    }
+    if let Some(wait) = task.wait {
+        object.insert("wait".into(), date(wait));
+    }
+    if let Some(estimate) = task.estimate {
+        object.insert("estimate".into(), estimate.to_string().into());
+    }
+    if let Some(assignee) = &task.assignee {
+        object.insert("assignee".into(), assignee.clone().into());
+    }
+    if let Some(parent) = task.parent.and_then(|uid| uuids.get(&uid)) {
+        object.insert(SUBTASK_OF.into(), (*parent).into());
+    }
+    let depends: Vec<Value> = task
+        .depends_on
+        .iter()
+        .filter_map(|uid| Some((*uuids.get(uid)?).into()))
+        .collect();
+    if !depends.is_empty() {
+        object.insert("depends".into(), depends.into());
+    }
+    let annotations: Vec<Value> = task
+        .notes
+        .iter()
+        .cloned()
+        .chain(task.links.iter().cloned())
+        .chain(task.checklist.iter().map(|item| item.to_string()))
+        .map(|description| {
+            let mut annotation = Map::new();
+            annotation.insert("entry".into(), date(entry));
+            annotation.insert("description".into(), description.into());
+            Value::Object(annotation)
+        })
+        .collect();
+    if !annotations.is_empty() {
+        object.insert("annotations".into(), annotations.into());
+    }
+    object
+}
+
+/// Reads the output of `task export`, either a JSON array or one JSON object per line.
+/// Tasks without a due date are due at 17:00 on the day of `now` in `tz`, and recurring
+/// templates are left out since the tasks they spawn are exported on their own. Input that isn't
+/// JSON fails as a whole, while tasks that can't be read are rejected with the line they start on
+pub(super) fn import<Tz: TimeZone>(
+    input: &str,
+    tz: &Tz,
+    now: DateTime<Utc>,
+) -> Result<Imported, ImportError> {
+    let default_deadline = end_of_today(tz, now);
+
+    let objects = objects(input)?;
+    // Parents and dependencies are given by UUID, so every task gets a uid for the others to
+    // refer to it by, which the import then replaces with one of its own
+    let uids: HashMap<String, usize> = objects
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, (_, object))| Some((object.get("uuid")?.as_str()?.into(), idx + 1)))
+        .collect();
+
+    let mut imported = Imported::default();
+    for (idx, (line, object)) in objects.into_iter().enumerate() {
+        if object.get("status").and_then(Value::as_str) == Some("recurring") {
+            continue;
+        }
+        let task = match object {
+            Value::Object(object) => from_taskwarrior(line, object, &uids, default_deadline),
+            _ => Err(ImportError::new(line, format!("{object} is not a task"))),
+        };
+        match task {
+            Ok(task) => imported.tasks.push(Task {
+                uid: Some(idx + 1),
+                ..task
+            }),
+            Err(e) => imported.rejected.push(e),
+        }
+    }
+    Ok(imported)
+}
+
+fn from_taskwarrior(
+    line: usize,
+    object: Map<String, Value>,
+    uids: &HashMap<String, usize>,
+    default_deadline: DateTime<Utc>,
+) -> Result<Task, ImportError> {
+    let mut task = Task {
+        deadline: default_deadline,
+        ..Default::default()
+    };
+    let mut started = false;
+    for (key, value) in object {
+        let invalid = || ImportError::new(line, format!("{value} is not a valid {key}"));
+        let date = || {
+            value
+                .as_str()
+                .and_then(|date| date.strip_suffix('Z'))
+                .and_then(|date| NaiveDateTime::parse_from_str(date, BASIC_DATE_TIME).ok())
+                .map(|date| date.and_utc())
+                .ok_or_else(invalid)
+        };
+        match key.as_str() {
+            "description" => task.name = storable(value.as_str().ok_or_else(invalid)?),
+            "uuid" => {
+                let uuid = value.as_str().ok_or_else(invalid)?;
+                task.fields.insert("uuid".into(), storable(uuid));
+            }
+            "status" => {
+                task.status = match value.as_str().ok_or_else(invalid)? {
+                    "pending" | "waiting" => Status::Todo,
+                    "completed" => Status::Done,
+                    "deleted" => Status::Cancelled,
+                    _ => return Err(invalid()),
+                }
+            }
+            "entry" => task.created = Some(date()?),
+            "due" => task.deadline = date()?,
+            "end" => task.completed = Some(date()?),
+            "start" => started = true,
+            "scheduled" => task.scheduled = Some(date()?),
+            "wait" => task.wait = Some(date()?),
+            "tags" => {
+                let tags: Vec<String> = value
+                    .as_array()
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|tag| storable(&tag.replace(',', " ")))
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+                task.tags = (!tags.is_empty()).then_some(tags);
+            }
+            "priority" => {
+                task.priority = match value.as_str().ok_or_else(invalid)? {
+                    "H" => Some(Priority::High),
+                    "M" => Some(Priority::Medium),
+                    "L" => Some(Priority::Low),
+                    "" => None,
+                    _ => return Err(invalid()),
+                }
+            }
+            "annotations" => task.notes.extend(
+                value
+                    .as_array()
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .filter_map(|annotation| annotation.get("description")?.as_str())
+                    .map(storable),
+            ),
+            "depends" => {
+                // Older versions write a comma separated string, newer ones an array
+                let uuids: Vec<String> = match &value {
+                    Value::String(uuids) => uuids.split(',').map(str::to_string).collect(),
+                    Value::Array(uuids) => uuids
+                        .iter()
+                        .filter_map(|uuid| Some(uuid.as_str()?.to_string()))
+                        .collect(),
+                    _ => return Err(invalid()),
+                };
+                for uuid in uuids {
+                    match uids.get(uuid.trim()) {
+                        Some(&uid) => task.depends_on.push(uid),
+                        // A task that wasn't exported along can only be named
+                        None => task
+                            .notes
+                            .push(format!("Depends on: {}", storable(uuid.trim()))),
+                    }
+                }
+            }
+            SUBTASK_OF => match value.as_str().and_then(|uuid| uids.get(uuid)) {
+                Some(&uid) => task.parent = Some(uid),
+                None => insert_field(&mut task, &key, value),
+            },
+            "estimate" => match value.as_str().and_then(|estimate| estimate.parse().ok()) {
+                Some(estimate) => task.estimate = Some(estimate),
+                None => insert_field(&mut task, &key, value),
+            },
+            "assignee" => task.assignee = value.as_str().map(storable),
+            _ if IGNORED.contains(&key.as_str()) => {}
+            // Anything else, like `project` or a UDA, is kept as a custom field
+            _ => insert_field(&mut task, &key, value),
+        }
+    }
+    if task.name.is_empty() {
+        return Err(ImportError::new(line, "task has no description"));
+    }
+    if started && task.status == Status::Todo {
+        task.status = Status::InProgress;
+    }
+    if task.is_open() {
+        task.completed = None;
+    }
+    Ok(task)
+}
+
+fn insert_field(task: &mut Task, key: &str, value: Value) {
+    let value = match value {
+        Value::String(value) => value,
+        Value::Number(value) => value.to_string(),
+        Value::Bool(value) => value.to_string(),
+        _ => return,
+    };
+    let name = storable(key).replace('=', "-");
+    if !value.is_empty() && !name.is_empty() {
+        task.fields.insert(name, storable(&value));
+    }
+}
+
+/// A task's JSON object, along with the line it starts on
+type TaskObject = (usize, Value);
+
+/// The top-level JSON values of `input`, whether in an array or one after another
+fn objects(input: &str) -> Result<Vec<TaskObject>, ImportError> {
+    let line_at = |offset: usize| input[..offset].matches('\n').count() + 1;
+    let skip_whitespace = |offset: usize| input.len() - input[offset..].trim_start().len();
+    let mut offset = skip_whitespace(0);
+    let in_array = input[offset..].starts_with('[');
+    if in_array {
+        offset = skip_whitespace(offset + 1);
+    }
+    let mut objects = Vec::new();
+    // serde reads one value at a time, leaving the commas and `]` of an array to be read here
+    while offset < input.len() && !(in_array && input[offset..].starts_with(']')) {
+        let mut stream = Deserializer::from_str(&input[offset..]).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(value)) => objects.push((line_at(offset), value)),
+            Some(Err(e)) => {
+                return Err(ImportError::new(
+                    line_at(offset) + e.line() - 1,
+                    e.to_string(),
+                ));
+            }
+            None => break,
+        }
+        offset = skip_whitespace(offset + stream.byte_offset());
+        if in_array {
+            match input[offset..].chars().next() {
+                Some(',') => offset = skip_whitespace(offset + 1),
+                Some(']') => {}
+                _ => return Err(ImportError::new(line_at(offset), "expected `,` or `]`")),
+            }
+        }
+    }
+    match input[offset..].strip_prefix(']') {
+        _ if !in_array => Ok(objects),
+        Some(rest) if rest.trim().is_empty() => Ok(objects),
+        Some(_) => Err(ImportError::new(
+            line_at(offset),
+            "unexpected text after `]`",
+        )),
+        None => Err(ImportError::new(
+            line_at(offset),
+            "input ends in the middle of the list",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::exchange::test_util::date_time;
+    use crate::task_tracker::task::{ChecklistItem, Estimate, WorkInterval};
+    use chrono::TimeDelta;
+
+    #[test]
+    fn import_taskwarrior_export() {
+        let imported = import(
+            include_str!("fixtures/taskwarrior.json"),
+            &Utc,
+            date_time("2025-03-10T09:00:00+00:00"),
+        )
+        .unwrap();
+        assert!(imported.rejected.is_empty());
+        let expected = vec![
+            Task {
+                name: "Call the plumber".into(),
+                tags: Some(vec!["home".into(), "phone".into()]),
+                deadline: date_time("2025-03-12T17:00:00+00:00"),
+                status: Status::InProgress,
+                uid: Some(1),
+                depends_on: vec![2],
+                priority: Some(Priority::High),
+                created: Some(date_time("2025-03-01T09:30:00+00:00")),
+                fields: [
+                    ("project".into(), "house.repairs".into()),
+                    ("uuid".into(), "a5b9e3e4-1c1f-4d2b-9a57-0f4b7c1d2e01".into()),
+                ]
+                .into(),
+                notes: vec!["Kitchen sink / bathroom".into()],
+                ..Default::default()
+            },
+            Task {
+                name: "Find the plumber's number".into(),
+                deadline: date_time("2025-03-10T17:00:00+00:00"),
+                status: Status::Done,
+                uid: Some(2),
+                created: Some(date_time("2025-03-01T09:00:00+00:00")),
+                completed: Some(date_time("2025-03-02T10:00:00+00:00")),
+                fields: [
+                    ("estimate_hours".into(), "0.5".into()),
+                    ("uuid".into(), "b8d2c1a0-7e6f-4a3b-8c9d-1e2f3a4b5c02".into()),
+                ]
+                .into(),
+                ..Default::default()
+            },
+            Task {
+                name: "Renew passport".into(),
+                deadline: date_time("2025-04-01T17:00:00+00:00"),
+                status: Status::Todo,
+                uid: Some(3),
+                created: Some(date_time("2025-03-03T08:00:00+00:00")),
+                wait: Some(date_time("2025-03-20T00:00:00+00:00")),
+                fields: [("uuid".into(), "d4e5f6a7-2b3c-4d5e-8f90-a1b2c3d4e5f6".into())].into(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(imported.tasks, expected);
+    }
+
+    #[test]
+    fn taskwarrior_round_trip() {
+        let now = date_time("2025-03-10T09:00:00+00:00");
+        let task = Task {
+            name: "Write \"release\" notes".into(),
+            tags: Some(vec!["docs".into()]),
+            deadline: date_time("2025-03-14T17:00:00+00:00"),
+            status: Status::Cancelled,
+            priority: Some(Priority::Low),
+            created: Some(date_time("2025-03-01T09:30:00+00:00")),
+            completed: Some(date_time("2025-03-05T09:30:00+00:00")),
+            estimate: Some(Estimate::Time(TimeDelta::minutes(90))),
+            assignee: Some("alice".into()),
+            fields: [
+                ("ticket".into(), "OPS-12".into()),
+                ("uuid".into(), "5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e03".into()),
+            ]
+            .into(),
+            notes: vec!["Keep it short".into()],
+            uid: Some(1),
+            ..Default::default()
+        };
+        let exported = export(std::slice::from_ref(&task), now);
+        assert!(exported.contains("\"uuid\":\"5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e03\""));
+        let imported = import(&exported, &Utc, now).unwrap();
+        assert_eq!(imported.tasks, vec![task]);
+    }
+
+    #[test]
+    fn taskwarrior_round_trip_keeps_subtasks_and_dependencies() {
+        let now = date_time("2025-03-10T09:00:00+00:00");
+        let task = |name: &str, uid: usize, uuid: &str| Task {
+            name: name.into(),
+            deadline: date_time("2025-03-14T17:00:00+00:00"),
+            created: Some(now),
+            uid: Some(uid),
+            fields: [("uuid".into(), uuid.into())].into(),
+            ..Default::default()
+        };
+        let parent = task("Move house", 7, "5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e07");
+        let subtask = Task {
+            parent: Some(7),
+            // Task 42 isn't exported along, so nothing can say which task it was
+            depends_on: vec![7, 42],
+            ..task("Pack books", 9, "5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e09")
+        };
+        let exported = export(&[parent, subtask], now);
+        assert!(exported.contains(
+            "\"depends\":[\"5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e07\"],\"description\":\"Pack books\""
+        ));
+        assert!(exported.contains("\"subtask_of\":\"5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e07\""));
+
+        let imported = import(&exported, &Utc, now).unwrap().tasks;
+        assert_eq!(
+            imported,
+            [
+                task("Move house", 1, "5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e07"),
+                Task {
+                    parent: Some(1),
+                    depends_on: vec![1],
+                    ..task("Pack books", 2, "5d0f2c3e-8b1a-4c6d-9e7f-1a2b3c4d5e09")
+                },
+            ]
+        );
+
+        let outside = r#"{"description": "Pack", "uuid": "u1", "depends": "u2"}"#;
+        let imported = import(outside, &Utc, now).unwrap().tasks;
+        assert_eq!(imported[0].depends_on, Vec::<usize>::new());
+        assert_eq!(imported[0].notes, ["Depends on: u2"]);
+    }
+
+    #[test]
+    fn export_extras_as_annotations() {
+        let now = date_time("2025-03-10T09:00:00+00:00");
+        let task = Task {
+            name: "Pack".into(),
+            deadline: now,
+            links: vec!["https://example.com/list".into()],
+            checklist: vec![ChecklistItem {
+                text: "charger".into(),
+                done: true,
+            }],
+            status: Status::InProgress,
+            work_log: vec![WorkInterval {
+                start: now,
+                end: None,
+            }],
+            ..Default::default()
+        };
+        let imported = import(&export(&[task], now), &Utc, now).unwrap().tasks;
+        assert_eq!(
+            imported[0].notes,
+            ["https://example.com/list", "[x] charger"]
+        );
+        assert_eq!(imported[0].status, Status::InProgress);
+    }
+
+    #[test]
+    fn import_taskwarrior_errors() {
+        let now = date_time("2025-03-10T09:00:00+00:00");
+        // A task that can't be read is rejected on its own, with the line it starts on
+        let input = "{\"description\":\"a\"}\n{\"description\":\"b\",\n\"status\":\"lost\"}\n";
+        let imported = import(input, &Utc, now).unwrap();
+        assert_eq!(imported.tasks.len(), 1);
+        assert_eq!(
+            imported.rejected,
+            [ImportError::new(2, "\"lost\" is not a valid status")]
+        );
+        let input = "[\n{\"description\":\"a\"},\n{\"description\":\"b\",\n\"due\":2},\n7\n]";
+        let imported = import(input, &Utc, now).unwrap();
+        assert_eq!(imported.tasks.len(), 1);
+        assert_eq!(
+            imported.rejected,
+            [
+                ImportError::new(3, "2 is not a valid due"),
+                ImportError::new(5, "7 is not a task")
+            ]
+        );
+        // Input that isn't JSON can't be read at all
+        let input = "[\n{\"description\":\"a\"},\n{\"description\":\"b\"\n";
+        assert!(import(input, &Utc, now).is_err());
+        let input = "[\n{\"description\":\"a\"}\n{\"description\":\"b\"}\n]";
+        assert_eq!(import(input, &Utc, now).unwrap_err().line, 3);
+        let input = "{\"description\":\"a\"}\n{\"description\": nope}";
+        assert_eq!(import(input, &Utc, now).unwrap_err().line, 2);
+    }
+}
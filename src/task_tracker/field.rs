@@ -0,0 +1,186 @@
+use chrono::NaiveDate;
+use std::{collections::HashMap, error::Error, fmt::Display, str::FromStr};
+
+use crate::config::{Config, ConfigError};
+use crate::task_tracker::task::check_text;
+
+/// What values a custom field accepts, declared in the config as `field.<name> = <type>`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    String,
+    Number,
+    Date,
+    /// One of a fixed set of values, declared as `enum:a,b,c`
+    Enum(Vec<String>),
+}
+
+impl FromStr for FieldType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            Some(("enum", values)) => Ok(FieldType::Enum(
+                values
+                    .split(',')
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect(),
+            )),
+            _ => match s.trim() {
+                "string" => Ok(FieldType::String),
+                "number" => Ok(FieldType::Number),
+                "date" => Ok(FieldType::Date),
+                _ => Err(format!(
+                    "\"{s}\" is not one of string, number, date or enum:<values>"
+                )),
+            },
+        }
+    }
+}
+
+impl FieldType {
+    /// Checks `value` and brings it to the form it is stored and compared in
+    fn normalize(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match self {
+            FieldType::String => {
+                (!value.is_empty() && check_text(value).is_ok()).then(|| value.into())
+            }
+            FieldType::Number => value.parse::<f64>().ok().map(|number| number.to_string()),
+            FieldType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.format("%Y-%m-%d").to_string()),
+            FieldType::Enum(values) => values.iter().find(|allowed| *allowed == value).cloned(),
+        }
+    }
+}
+
+/// The custom fields declared in the config, keyed by name
+#[derive(Debug, Default, PartialEq)]
+pub struct FieldDefinitions {
+    fields: HashMap<String, FieldType>,
+}
+
+impl TryFrom<&Config> for FieldDefinitions {
+    type Error = ConfigError;
+
+    fn try_from(config: &Config) -> Result<Self, Self::Error> {
+        let fields = config
+            .section("field")
+            .map(|(name, field_type)| {
+                field_type
+                    .parse()
+                    .map(|field_type| (name.to_string(), field_type))
+                    .map_err(|_| {
+                        ConfigError::InvalidValue(format!("field.{name}"), field_type.into())
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FieldDefinitions { fields })
+    }
+}
+
+impl FieldDefinitions {
+    /// Parses a `key=value` assignment against the declared fields
+    pub fn parse(&self, assignment: &str) -> Result<(String, String), FieldError> {
+        let (name, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| FieldError::InvalidAssignment(assignment.into()))?;
+        let name = name.trim();
+        let field_type = self
+            .fields
+            .get(name)
+            .ok_or_else(|| FieldError::Undeclared(name.into()))?;
+        let value = field_type
+            .normalize(value)
+            .ok_or_else(|| FieldError::InvalidValue(name.into(), value.trim().into()))?;
+        Ok((name.into(), value))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FieldError {
+    InvalidAssignment(String),
+    Undeclared(String),
+    InvalidValue(String, String),
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            FieldError::InvalidAssignment(ref assignment) => {
+                write!(f, "\"{assignment}\" is not of the form `key=value`")
+            }
+            FieldError::Undeclared(ref name) => {
+                write!(f, "no field.{name} is declared in the config file")
+            }
+            FieldError::InvalidValue(ref name, ref value) => {
+                write!(f, "\"{value}\" is not a valid value for field {name}")
+            }
+        }
+    }
+}
+
+impl Error for FieldError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn definitions() -> FieldDefinitions {
+        let config = Config::from_str(
+            "field.ticket = string\nfield.cost = number\nfield.due_review = date\nfield.severity = enum:low, high\n",
+        )
+        .unwrap();
+        FieldDefinitions::try_from(&config).unwrap()
+    }
+
+    #[test]
+    fn field_types_from_config() {
+        assert_eq!(
+            definitions().fields.get("severity"),
+            Some(&FieldType::Enum(vec!["low".into(), "high".into()]))
+        );
+        let config = Config::from_str("field.ticket = text").unwrap();
+        assert!(matches!(
+            FieldDefinitions::try_from(&config),
+            Err(ConfigError::InvalidValue(_, _))
+        ));
+    }
+
+    #[test]
+    fn parse_field_assignments() {
+        let definitions = definitions();
+        assert_eq!(
+            definitions.parse("ticket=OPS-12"),
+            Ok(("ticket".into(), "OPS-12".into()))
+        );
+        assert_eq!(
+            definitions.parse("cost = 3.50"),
+            Ok(("cost".into(), "3.5".into()))
+        );
+        assert_eq!(
+            definitions.parse("due_review=2025-03-01"),
+            Ok(("due_review".into(), "2025-03-01".into()))
+        );
+        assert_eq!(
+            definitions.parse("severity=medium"),
+            Err(FieldError::InvalidValue("severity".into(), "medium".into()))
+        );
+        assert_eq!(
+            definitions.parse("customer=acme"),
+            Err(FieldError::Undeclared("customer".into()))
+        );
+        assert_eq!(
+            definitions.parse("ticket"),
+            Err(FieldError::InvalidAssignment("ticket".into()))
+        );
+        assert_eq!(
+            definitions.parse("ticket=OPS-1\nOPS-2"),
+            Err(FieldError::InvalidValue(
+                "ticket".into(),
+                "OPS-1\nOPS-2".into()
+            ))
+        );
+    }
+}
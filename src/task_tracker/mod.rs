@@ -1,23 +1,269 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
+use exchange::{ExchangeFormat, ExportOptions, ImportOptions};
+use report::ReportGrouping;
+use selection::Selection;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, path::Path, str::FromStr};
+use task::{Estimate, Priority, Status};
+use urgency::UrgencyWeights;
 
+pub mod convert;
+pub mod document_task_tracker;
+pub mod exchange;
+pub mod field;
 pub mod plain_text_task_tracker;
+pub mod report;
+pub mod selection;
+pub mod storage;
 pub mod task;
+pub mod todo_txt_task_tracker;
+pub mod urgency;
+
+/// Everything given when adding a task, where ids are the ones displayed by `list`
+pub struct NewTask {
+    pub name: String,
+    pub tags: Option<Vec<String>>,
+    pub deadline: DateTime<Utc>,
+    pub parent: Option<usize>,
+    pub depends_on: Option<Vec<usize>>,
+    pub priority: Option<Priority>,
+    pub estimate: Option<Estimate>,
+    pub scheduled: Option<DateTime<Utc>>,
+    pub wait: Option<DateTime<Utc>>,
+    pub assignee: Option<String>,
+    /// Custom fields, already checked against their declared types
+    pub fields: Vec<(String, String)>,
+    pub links: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// Which tasks `list` shows
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ListFilter {
+    /// Show completed tasks too, without ids
+    pub all: bool,
+    pub overdue: bool,
+    /// Only tasks with every one of these tags
+    pub tags: Option<Vec<String>>,
+    /// Only incomplete tasks that aren't blocked
+    pub ready: bool,
+    /// Show tasks whose wait date hasn't passed too
+    pub deferred: bool,
+    /// Only tasks scheduled within `from..until`
+    pub scheduled: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Only tasks snoozed at least this many times
+    pub snoozed_often: Option<u32>,
+    /// Only tasks assigned to this person
+    pub assignee: Option<String>,
+    /// Only waiting tasks, longest waiting first
+    pub waiting: bool,
+    /// Only tasks whose custom fields have these values
+    pub fields: Vec<(String, String)>,
+    /// Custom fields shown as extra columns
+    pub columns: Vec<String>,
+    /// List archived tasks instead
+    pub archived: bool,
+    /// Only tasks whose name or notes contain this text, ignoring case
+    pub search: Option<String>,
+}
+
+/// Changes `edit` makes to every selected task, leaving what isn't given as it is
+#[derive(Debug, Default)]
+pub struct TaskEdit {
+    pub name: Option<String>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    pub priority: Option<Priority>,
+    pub assignee: Option<String>,
+    /// Custom fields, already checked against their declared types
+    pub fields: Vec<(String, String)>,
+}
+
+/// How to push back a deadline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Snooze {
+    By(TimeDelta),
+    Until(DateTime<Utc>),
+}
+
+/// Which task a command is about: the open task shown with an id, or any task by its uid, which
+/// stays the same once the task is closed or archived
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskRef {
+    Id(usize),
+    Uid(usize),
+}
+
+/// How the task file lays out its tasks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileFormat {
+    #[default]
+    Table,
+    TodoTxt,
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl FileFormat {
+    /// The format a file's extension names, if any
+    pub fn from_extension(path: &Path) -> Option<FileFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(FileFormat::Json),
+            "yaml" | "yml" => Some(FileFormat::Yaml),
+            "toml" => Some(FileFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FileFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            FileFormat::Table => "table".fmt(f),
+            FileFormat::TodoTxt => "todo.txt".fmt(f),
+            FileFormat::Json => "json".fmt(f),
+            FileFormat::Yaml => "yaml".fmt(f),
+            FileFormat::Toml => "toml".fmt(f),
+        }
+    }
+}
+
+impl FromStr for FileFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "table" => Ok(FileFormat::Table),
+            "todo.txt" | "todotxt" | "todo" => Ok(FileFormat::TodoTxt),
+            "json" => Ok(FileFormat::Json),
+            "yaml" | "yml" => Ok(FileFormat::Yaml),
+            "toml" => Ok(FileFormat::Toml),
+            _ => Err(format!(
+                "\"{s}\" is not one of table, todo.txt, json, yaml or toml"
+            )),
+        }
+    }
+}
+
+/// How `show` prints a task
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            OutputFormat::Text => "text".fmt(f),
+            OutputFormat::Json => "json".fmt(f),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("\"{s}\" is not one of text or json")),
+        }
+    }
+}
+
+/// A task as `list` shows it, with the id commands take, which only incomplete tasks have
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ListedTask {
+    pub id: Option<usize>,
+    #[serde(flatten)]
+    pub task: task::Task,
+}
+
+/// Every task of a task file and of its archive, as `convert` moves them to another format
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TaskSet {
+    pub tasks: Vec<task::Task>,
+    pub archived: Vec<task::Task>,
+}
 
 pub trait TaskTracker {
     type Err;
 
-    fn add_task(
+    /// Returns the uid the new task was given
+    fn add_task(&self, new_task: NewTask) -> Result<usize, Self::Err>;
+    /// Bulk commands ask before changing more than one task, unless `yes` is set
+    fn complete_task(
+        &mut self,
+        selection: Selection,
+        cascade: bool,
+        yes: bool,
+    ) -> Result<(), Self::Err>;
+    fn delete_task(&mut self, selection: Selection, yes: bool) -> Result<(), Self::Err>;
+    fn edit_task(
+        &mut self,
+        selection: Selection,
+        edit: TaskEdit,
+        yes: bool,
+    ) -> Result<(), Self::Err>;
+    fn set_status(&mut self, task: TaskRef, status: Status) -> Result<(), Self::Err>;
+    fn add_checklist_item(&mut self, id: usize, text: String) -> Result<(), Self::Err>;
+    fn check_item(&mut self, id: usize, item: usize, done: bool) -> Result<(), Self::Err>;
+    fn show_task(&self, task: TaskRef, format: OutputFormat) -> Result<(), Self::Err>;
+    fn add_links(&mut self, id: usize, links: Vec<String>) -> Result<(), Self::Err>;
+    /// Opens the task's links with the system opener, or only prints them when `print` is set
+    fn open_task(&self, id: usize, print: bool) -> Result<(), Self::Err>;
+    fn wait_for(&mut self, id: usize, who: String) -> Result<(), Self::Err>;
+    fn snooze_task(
+        &mut self,
+        selection: Selection,
+        snooze: Snooze,
+        yes: bool,
+    ) -> Result<(), Self::Err>;
+    fn add_dependencies(&mut self, id: usize, depends_on: Vec<usize>) -> Result<(), Self::Err>;
+    fn list_task(&self, filter: ListFilter) -> Result<(), Self::Err>;
+    /// The tasks `list` shows, in the same order, rather than printed as a table
+    fn listed_tasks(&self, filter: ListFilter) -> Result<Vec<ListedTask>, Self::Err>;
+    /// Moves closed tasks out of the task file, only those closed longer than `older_than` ago
+    fn archive_tasks(&mut self, older_than: Option<TimeDelta>) -> Result<(), Self::Err>;
+    /// Prints every task in `format`
+    fn export_tasks(
+        &mut self,
+        format: ExchangeFormat,
+        options: ExportOptions,
+    ) -> Result<(), Self::Err>;
+    /// Adds the tasks read from `input`, skipping any exported from this task file before
+    fn import_tasks(
+        &mut self,
+        format: ExchangeFormat,
+        input: &str,
+        options: ImportOptions,
+    ) -> Result<(), Self::Err>;
+    fn start_task(&mut self, id: usize) -> Result<(), Self::Err>;
+    fn stop_task(&mut self) -> Result<(), Self::Err>;
+    fn time_report(
         &self,
-        name: String,
-        tags: Option<Vec<String>>,
-        deadline: DateTime<Utc>,
+        since: Option<DateTime<Utc>>,
+        grouping: ReportGrouping,
     ) -> Result<(), Self::Err>;
-    fn complete_task(&mut self, id: usize) -> Result<(), Self::Err>;
-    fn delete_task(&mut self, id: usize) -> Result<(), Self::Err>;
-    fn list_task(
+    fn estimate_report(
         &self,
-        incomplete: bool,
-        overdue: bool,
-        tags: Option<Vec<String>>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
     ) -> Result<(), Self::Err>;
+    fn load_report(&self, available: TimeDelta) -> Result<(), Self::Err>;
+    fn next_task(&self, count: usize, weights: &UrgencyWeights) -> Result<(), Self::Err>;
+    /// Where the task file and its archive are read and written
+    fn storage(&self) -> &dyn storage::Storage;
+    /// Whether the task file or its archive already exists
+    fn exists(&self) -> Result<bool, Self::Err>;
+    fn read_all(&self) -> Result<TaskSet, Self::Err>;
+    /// Replaces the task file and its archive with exactly `set`, archiving nothing on the way
+    fn write_all(&mut self, set: &TaskSet) -> Result<(), Self::Err>;
 }
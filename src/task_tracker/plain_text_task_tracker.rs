@@ -1,78 +1,980 @@
-use chrono::{DateTime, Utc};
-
-use crate::task_tracker::TaskTracker;
-use crate::task_tracker::task::{ParseTaskError, Task};
-use crate::utils::{TextEffect, add_text_effect};
+use chrono::{DateTime, Local, TimeDelta, TimeZone, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::task_tracker::exchange::{
+    self, ExchangeFormat, ExportOptions, ImportError, ImportOptions,
+};
+use crate::task_tracker::report::{
+    ReportGrouping, estimates_by_tag, format_hours, time_by, week_end,
+};
+use crate::task_tracker::selection::Selection;
+use crate::task_tracker::storage::{Conflict, Files, Storage, TaskFile};
+use crate::task_tracker::task::{ChecklistItem, ParseTaskError, Status, Task};
+use crate::task_tracker::urgency::{UrgencyWeights, urgency};
+use crate::task_tracker::{
+    ListFilter, ListedTask, NewTask, OutputFormat, Snooze, TaskEdit, TaskRef, TaskSet, TaskTracker,
+};
+use crate::utils::{
+    TableRow, TextEffect, add_text_effect, confirm, format_duration, open_external, print_table,
+};
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
-use std::fs::OpenOptions;
-use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::path::PathBuf;
+use std::io::{BufRead, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// How a task file lays out its tasks
+pub trait TaskFileFormat {
+    /// Whether a task can be added at the end of the file without rewriting what is before it,
+    /// as in formats with one task per line
+    const APPENDABLE: bool = true;
+
+    /// Reads every task, sorted by deadline
+    fn read_tasks<B: BufRead>(reader: B) -> Result<Vec<Task>, ParseTaskError>;
+    /// Writes one task after those already written, which only `APPENDABLE` formats allow
+    fn append_task<W: Write>(writer: &mut W, task: &Task) -> Result<(), std::io::Error>;
+
+    fn write_tasks<W: Write>(writer: &mut W, tasks: &[&Task]) -> Result<(), std::io::Error> {
+        for task in tasks {
+            Self::append_task(writer, task)?;
+        }
+        Ok(())
+    }
+
+    /// The archive lives next to the task file, e.g. `tasks.archive.txt` for `tasks.txt`
+    fn archive_path(file_path: &Path) -> PathBuf {
+        let mut name = file_path.file_stem().unwrap_or_default().to_owned();
+        name.push(".archive");
+        if let Some(extension) = file_path.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        file_path.with_file_name(name)
+    }
+}
+
+/// The format tasks are kept in by default, a table with `|` between columns
+pub struct PipeTable;
 
-pub struct PlainTextTaskTracker {
+impl TaskFileFormat for PipeTable {
+    fn read_tasks<B: BufRead>(reader: B) -> Result<Vec<Task>, ParseTaskError> {
+        PlainTextTaskTracker::read_tasks_from_file(reader)
+    }
+
+    fn append_task<W: Write>(writer: &mut W, task: &Task) -> Result<(), std::io::Error> {
+        PlainTextTaskTracker::add_task_logic(writer, task)
+    }
+
+    fn write_tasks<W: Write>(writer: &mut W, tasks: &[&Task]) -> Result<(), std::io::Error> {
+        PlainTextTaskTracker::write_tasks_to_file(writer, tasks)
+    }
+}
+
+pub struct PlainTextTaskTracker<F = PipeTable> {
     file_path: PathBuf,
+    /// Closed tasks are moved to the archive whenever tasks are stored this long after closing
+    auto_archive: Option<TimeDelta>,
+    storage: Box<dyn Storage>,
+    format: PhantomData<F>,
 }
 
-impl PlainTextTaskTracker {
+impl<F: TaskFileFormat> PlainTextTaskTracker<F> {
     pub fn new(file_path: impl Into<PathBuf>) -> Self {
+        let file_path = file_path.into();
         PlainTextTaskTracker {
-            file_path: file_path.into(),
+            storage: Box::new(Files {
+                archive: F::archive_path(&file_path),
+                tasks: file_path.clone(),
+            }),
+            file_path,
+            auto_archive: None,
+            format: PhantomData,
         }
     }
 
+    pub fn with_auto_archive(mut self, after: Option<TimeDelta>) -> Self {
+        self.auto_archive = after;
+        self
+    }
+
+    /// Reads and writes the files through `storage` rather than directly
+    pub fn with_storage(mut self, storage: Box<dyn Storage>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    pub fn archive_path(&self) -> PathBuf {
+        F::archive_path(&self.file_path)
+    }
+
+    fn load(&self) -> Result<Vec<Task>, PlainTextTaskTrackerError> {
+        let contents = self.storage.read(TaskFile::Tasks)?;
+        Ok(F::read_tasks(contents.as_slice())?)
+    }
+
+    /// The tasks of the task file, none when it doesn't exist yet
+    fn load_or_default(&self) -> Result<Vec<Task>, PlainTextTaskTrackerError> {
+        match self.storage.exists(TaskFile::Tasks)? {
+            true => self.load(),
+            false => Ok(Vec::new()),
+        }
+    }
+
+    fn load_archive(&self) -> Result<Vec<Task>, PlainTextTaskTrackerError> {
+        match self.storage.exists(TaskFile::Archive)? {
+            true => {
+                let contents = self.storage.read(TaskFile::Archive)?;
+                Ok(F::read_tasks(contents.as_slice())?)
+            }
+            false => Ok(Vec::new()),
+        }
+    }
+
+    /// Replaces `file` with exactly `tasks`
+    fn write(&self, file: TaskFile, tasks: &[&Task]) -> Result<(), PlainTextTaskTrackerError> {
+        let mut contents = Vec::new();
+        F::write_tasks(&mut contents, tasks)?;
+        Ok(self.storage.write(file, &contents)?)
+    }
+
+    /// Tasks from both the task file and the archive, for reports on past work
+    fn load_with_archive(&self) -> Result<Vec<Task>, PlainTextTaskTrackerError> {
+        let mut tasks = self.load()?;
+        tasks.extend(self.load_archive()?);
+        tasks.sort_by_key(|task| task.deadline);
+        Ok(tasks)
+    }
+
+    fn store(&self, tasks: &[Task]) -> Result<(), PlainTextTaskTrackerError> {
+        let archived = match self.auto_archive {
+            Some(after) => PlainTextTaskTracker::archive_logic(tasks, Utc::now() - after),
+            None => Vec::new(),
+        };
+        self.store_archiving(tasks, &archived)
+    }
+
+    /// Stores `tasks`, moving the ones at positions `archived` to the archive
+    fn store_archiving(
+        &self,
+        tasks: &[Task],
+        archived: &[usize],
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        // The archive is written first so a failure can duplicate tasks but never lose them
+        if !archived.is_empty() && F::APPENDABLE {
+            let mut contents = Vec::new();
+            for &idx in archived {
+                F::append_task(&mut contents, &tasks[idx])?;
+            }
+            self.storage.append(TaskFile::Archive, &contents)?;
+        } else if !archived.is_empty() {
+            let archive = self.load_archive()?;
+            let mut all: Vec<&Task> = archive.iter().collect();
+            all.extend(archived.iter().map(|&idx| &tasks[idx]));
+            self.write(TaskFile::Archive, &all)?;
+        }
+
+        let kept: Vec<&Task> = tasks
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !archived.contains(idx))
+            .map(|(_, task)| task)
+            .collect();
+        self.write(TaskFile::Tasks, &kept)
+    }
+}
+
+impl PlainTextTaskTracker {
     fn read_tasks_from_file<B: BufRead>(reader: B) -> Result<Vec<Task>, ParseTaskError> {
         let mut tasks: Vec<Task> = reader
             .lines()
             .map_while(Result::ok)
             .map(|line| line.parse::<Task>())
             .collect::<Result<Vec<Task>, ParseTaskError>>()?;
-        tasks.sort_by(|task_a, task_b| task_a.deadline.cmp(&task_b.deadline));
+        tasks.sort_by_key(|task| task.deadline);
         Ok(tasks)
     }
 
-    fn write_tasks_to_file<W: Write>(writer: &mut W, tasks: &[Task]) -> Result<(), std::io::Error> {
+    fn write_tasks_to_file<W: Write, T: std::borrow::Borrow<Task>>(
+        writer: &mut W,
+        tasks: &[T],
+    ) -> Result<(), std::io::Error> {
         for task in tasks {
-            writeln!(writer, "{task}")?
+            writeln!(writer, "{}", task.borrow())?
         }
         Ok(())
     }
 
-    fn add_task_logic<W: Write>(writer: &mut W, task: Task) -> Result<(), std::io::Error> {
+    fn add_task_logic<W: Write>(writer: &mut W, task: &Task) -> Result<(), std::io::Error> {
         writeln!(writer, "{task}")?;
         Ok(())
     }
 
-    fn complete_task_logic(tasks: &mut [Task], id: usize) {
+    /// Gives every task written before uids existed one, returning whether any were missing
+    fn assign_missing_uids(tasks: &mut [Task]) -> bool {
+        let first_uid = PlainTextTaskTracker::next_uid(tasks);
+        let mut assigned = false;
+        for (uid, task) in (first_uid..).zip(tasks.iter_mut().filter(|task| task.uid.is_none())) {
+            task.uid = Some(uid);
+            assigned = true;
+        }
+        assigned
+    }
+
+    /// Gives every task without a UUID a random one, which tells it apart from the tasks of every
+    /// other task file once exported, returning whether any were missing
+    fn assign_missing_uuids(tasks: &mut [Task]) -> bool {
+        let mut assigned = false;
+        for task in tasks.iter_mut() {
+            if !task.fields.contains_key("uuid") {
+                task.fields
+                    .insert("uuid".into(), Uuid::new_v4().hyphenated().to_string());
+                assigned = true;
+            }
+        }
+        assigned
+    }
+
+    fn next_uid(tasks: &[Task]) -> usize {
+        tasks.iter().filter_map(|task| task.uid).max().unwrap_or(0) + 1
+    }
+
+    /// Position in `tasks` of the incomplete task displayed with `id`
+    fn incomplete_task_index(tasks: &[Task], id: usize) -> Option<usize> {
         tasks
-            .iter_mut()
-            .filter(|task| !task.complete)
+            .iter()
             .enumerate()
-            .filter(|(idx, _)| *idx == id)
-            .for_each(|(_, task)| task.complete());
+            .filter(|(_, task)| task.is_open())
+            .nth(id)
+            .map(|(idx, _)| idx)
+    }
+
+    /// Position in `tasks` of the task `task` refers to, which only an open task can be by id
+    fn task_index(tasks: &[Task], task: TaskRef) -> Result<usize, PlainTextTaskTrackerError> {
+        match task {
+            TaskRef::Id(id) => PlainTextTaskTracker::incomplete_task_index(tasks, id)
+                .ok_or(PlainTextTaskTrackerError::TaskNotFound(id)),
+            TaskRef::Uid(uid) => tasks
+                .iter()
+                .position(|task| task.uid == Some(uid))
+                .ok_or(PlainTextTaskTrackerError::UidNotFound(uid)),
+        }
     }
 
-    fn delete_task_logic(tasks: &mut Vec<Task>, id: usize) {
-        if let Some((_, delete_idx)) = tasks
+    /// Positions in `tasks` of every subtask below `tasks[idx]`, at any depth
+    fn descendant_indices(tasks: &[Task], idx: usize) -> Vec<usize> {
+        let mut descendants = Vec::new();
+        let mut pending = vec![idx];
+        while let Some(current) = pending.pop() {
+            let Some(uid) = tasks[current].uid else {
+                continue;
+            };
+            for (child_idx, child) in tasks.iter().enumerate() {
+                if child.parent == Some(uid)
+                    && child_idx != idx
+                    && !descendants.contains(&child_idx)
+                {
+                    descendants.push(child_idx);
+                    pending.push(child_idx);
+                }
+            }
+        }
+        descendants
+    }
+
+    /// Adds imported tasks, skipping those whose UUID is taken in the task file or the archive as
+    /// they were imported or exported before, and returns how many were added. Imported uids are
+    /// only how the imported tasks refer to each other, so every added task gets a uid of its own
+    /// and its parent and dependencies are remapped to those
+    fn import_logic(tasks: &mut Vec<Task>, archive: &[Task], imported: Vec<Task>) -> usize {
+        let count = tasks.len();
+        // The uid of every imported task that has one, and the uid that task has here
+        let mut uids: HashMap<usize, usize> = HashMap::new();
+        for mut task in imported {
+            let imported_uid = task.uid.take();
+            let known = task.fields.get("uuid").and_then(|uuid| {
+                tasks
+                    .iter()
+                    .chain(archive)
+                    .find(|other| other.fields.get("uuid") == Some(uuid))
+                    .map(|other| other.uid)
+            });
+            let uid = match known {
+                Some(uid) => uid,
+                None => {
+                    let uid = PlainTextTaskTracker::next_uid(tasks)
+                        .max(PlainTextTaskTracker::next_uid(archive));
+                    task.uid = Some(uid);
+                    tasks.push(task);
+                    Some(uid)
+                }
+            };
+            if let (Some(from), Some(to)) = (imported_uid, uid) {
+                uids.insert(from, to);
+            }
+        }
+        // References to tasks that were not imported along would point at unrelated tasks here
+        for task in &mut tasks[count..] {
+            task.parent = task.parent.and_then(|parent| uids.get(&parent).copied());
+            task.depends_on = task
+                .depends_on
+                .iter()
+                .filter_map(|dependency| uids.get(dependency).copied())
+                .collect();
+        }
+        tasks.len() - count
+    }
+
+    /// Positions in `tasks` of the closed tasks that closed before `cutoff`, leaving out any with
+    /// open subtasks so no open task loses its parent
+    fn archive_logic(tasks: &[Task], cutoff: DateTime<Utc>) -> Vec<usize> {
+        tasks
             .iter()
-            .scan((None, None), |(incomplete_idx, total_idx), task| {
-                *total_idx = (*total_idx).map(|idx| idx + 1).or(Some(0));
-                if !task.complete {
-                    *incomplete_idx = (*incomplete_idx).map(|idx| idx + 1).or(Some(0));
+            .enumerate()
+            .filter(|(idx, task)| {
+                !task.is_open()
+                    && task.completed.is_none_or(|completed| completed <= cutoff)
+                    && PlainTextTaskTracker::descendant_indices(tasks, *idx)
+                        .iter()
+                        .all(|&descendant| !tasks[descendant].is_open())
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Positions in `tasks` of the selected tasks, resolved up front since ids shift as tasks close
+    fn select_logic(
+        tasks: &[Task],
+        selection: &Selection,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<usize>, PlainTextTaskTrackerError> {
+        let mut indices: Vec<usize> = match selection.ids.is_empty() {
+            true => (0..tasks.len())
+                .filter(|&idx| tasks[idx].is_open())
+                .collect(),
+            false => selection
+                .ids
+                .iter()
+                .flat_map(|range| range.0.clone())
+                .map(|id| {
+                    PlainTextTaskTracker::incomplete_task_index(tasks, id)
+                        .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        indices.retain(|&idx| {
+            selection
+                .conditions
+                .iter()
+                .all(|condition| condition.matches(&tasks[idx], now))
+        });
+        indices.sort_unstable();
+        indices.dedup();
+        match indices.is_empty() {
+            true => Err(PlainTextTaskTrackerError::NothingSelected),
+            false => Ok(indices),
+        }
+    }
+
+    /// Lists the tasks about to be changed and asks to go ahead, unless it's a single id or `yes` is set
+    fn confirm_selection(
+        tasks: &[Task],
+        indices: &[usize],
+        selection: &Selection,
+        action: &str,
+        yes: bool,
+    ) -> bool {
+        if yes || selection.is_single() {
+            return true;
+        }
+        println!("About to {action} {} task(s):", indices.len());
+        for &idx in indices {
+            println!("  {}", tasks[idx].name);
+        }
+        confirm("Go ahead?")
+    }
+
+    fn complete_task_logic(
+        tasks: &mut [Task],
+        indices: &[usize],
+        cascade: bool,
+        now: DateTime<Utc>,
+    ) {
+        for &idx in indices {
+            tasks[idx].complete(now);
+            if cascade {
+                for descendant in PlainTextTaskTracker::descendant_indices(tasks, idx) {
+                    if tasks[descendant].is_open() {
+                        tasks[descendant].complete(now);
+                    }
+                }
+            }
+        }
+    }
+
+    fn delete_task_logic(tasks: &mut Vec<Task>, indices: &[usize]) {
+        // Removing from the back keeps the remaining indices valid
+        for &delete_idx in indices.iter().rev() {
+            let deleted = tasks.remove(delete_idx);
+            if let Some(uid) = deleted.uid {
+                for task in tasks.iter_mut() {
+                    if task.parent == Some(uid) {
+                        task.parent = deleted.parent;
+                    }
+                    task.depends_on.retain(|&dependency| dependency != uid);
+                }
+            }
+        }
+    }
+
+    fn edit_task_logic(tasks: &mut [Task], indices: &[usize], edit: &TaskEdit) {
+        for &idx in indices {
+            let task = &mut tasks[idx];
+            if let Some(name) = &edit.name {
+                task.name = name.clone();
+            }
+            if let Some(deadline) = edit.deadline {
+                task.deadline = deadline;
+            }
+            if !edit.add_tags.is_empty() || !edit.remove_tags.is_empty() {
+                let mut tags = task.tags.take().unwrap_or_default();
+                for tag in &edit.add_tags {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+                tags.retain(|tag| !edit.remove_tags.contains(tag));
+                task.tags = (!tags.is_empty()).then_some(tags);
+            }
+            if let Some(priority) = edit.priority {
+                task.priority = Some(priority);
+            }
+            if let Some(assignee) = &edit.assignee {
+                task.assignee = Some(assignee.clone());
+            }
+            for (name, value) in &edit.fields {
+                task.fields.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    fn set_status_logic(
+        tasks: &mut [Task],
+        task: TaskRef,
+        status: Status,
+        now: DateTime<Utc>,
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        let idx = PlainTextTaskTracker::task_index(tasks, task)?;
+        tasks[idx].set_status(status, now);
+        Ok(())
+    }
+
+    fn add_checklist_item_logic(
+        tasks: &mut [Task],
+        id: usize,
+        text: String,
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        let idx = PlainTextTaskTracker::incomplete_task_index(tasks, id)
+            .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))?;
+        tasks[idx]
+            .checklist
+            .push(ChecklistItem { text, done: false });
+        Ok(())
+    }
+
+    /// Checks off (or unchecks) the checklist item numbered `item`, counting from 1
+    fn check_item_logic(
+        tasks: &mut [Task],
+        id: usize,
+        item: usize,
+        done: bool,
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        let idx = PlainTextTaskTracker::incomplete_task_index(tasks, id)
+            .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))?;
+        let checklist_item = item
+            .checked_sub(1)
+            .and_then(|position| tasks[idx].checklist.get_mut(position))
+            .ok_or(PlainTextTaskTrackerError::ChecklistItemNotFound(id, item))?;
+        checklist_item.done = done;
+        Ok(())
+    }
+
+    fn add_links_logic(
+        tasks: &mut [Task],
+        id: usize,
+        links: Vec<String>,
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        let idx = PlainTextTaskTracker::incomplete_task_index(tasks, id)
+            .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))?;
+        for link in links {
+            if !tasks[idx].links.contains(&link) {
+                tasks[idx].links.push(link);
+            }
+        }
+        Ok(())
+    }
+
+    fn wait_for_logic(
+        tasks: &mut [Task],
+        id: usize,
+        who: String,
+        now: DateTime<Utc>,
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        let idx = PlainTextTaskTracker::incomplete_task_index(tasks, id)
+            .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))?;
+        tasks[idx].wait_for(who, now);
+        Ok(())
+    }
+
+    fn snooze_task_logic(
+        tasks: &mut [Task],
+        indices: &[usize],
+        snooze: Snooze,
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        // Every deadline is worked out first, so a snooze too far leaves all tasks as they were
+        let deadlines = indices
+            .iter()
+            .map(|&idx| match snooze {
+                Snooze::By(delta) => {
+                    tasks[idx]
+                        .deadline
+                        .checked_add_signed(delta)
+                        .ok_or_else(|| {
+                            PlainTextTaskTrackerError::SnoozeTooFar(tasks[idx].name.clone())
+                        })
                 }
-                Some((*incomplete_idx, *total_idx))
+                Snooze::Until(deadline) => Ok(deadline),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (&idx, deadline) in indices.iter().zip(deadlines) {
+            tasks[idx].deadline = deadline;
+            tasks[idx].snoozed += 1;
+        }
+        Ok(())
+    }
+
+    /// Makes the task displayed with `id` depend on the tasks displayed with `depends_on`
+    fn add_dependencies_logic(
+        tasks: &mut [Task],
+        id: usize,
+        depends_on: &[usize],
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        let idx = PlainTextTaskTracker::incomplete_task_index(tasks, id)
+            .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))?;
+        let uids = PlainTextTaskTracker::resolve_uids(tasks, depends_on)?;
+        let uid = tasks[idx]
+            .uid
+            .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))?;
+
+        for dependency in uids {
+            if PlainTextTaskTracker::depends_on_transitively(tasks, dependency, uid) {
+                return Err(PlainTextTaskTrackerError::DependencyCycle(id));
+            }
+            if !tasks[idx].depends_on.contains(&dependency) {
+                tasks[idx].depends_on.push(dependency);
+            }
+        }
+        Ok(())
+    }
+
+    /// `uid`s of the incomplete tasks displayed with `ids`
+    fn resolve_uids(
+        tasks: &[Task],
+        ids: &[usize],
+    ) -> Result<Vec<usize>, PlainTextTaskTrackerError> {
+        ids.iter()
+            .map(|&id| {
+                PlainTextTaskTracker::incomplete_task_index(tasks, id)
+                    .and_then(|idx| tasks[idx].uid)
+                    .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))
             })
-            .flat_map(|(incomplete_idx, total_idx)| Some((incomplete_idx?, total_idx?)))
-            .find(|(incomplete_idx, _)| *incomplete_idx == id)
+            .collect()
+    }
+
+    /// Whether the task with uid `from` has to wait on the task with uid `to`, directly or not
+    fn depends_on_transitively(tasks: &[Task], from: usize, to: usize) -> bool {
+        let mut seen = Vec::new();
+        let mut pending = vec![from];
+        while let Some(current) = pending.pop() {
+            if current == to {
+                return true;
+            }
+            if seen.contains(&current) {
+                continue;
+            }
+            seen.push(current);
+            if let Some(task) = tasks.iter().find(|task| task.uid == Some(current)) {
+                pending.extend(&task.depends_on);
+            }
+        }
+        false
+    }
+
+    /// Stops whichever timer is running and starts one on the task displayed with `id`
+    fn start_task_logic(
+        tasks: &mut [Task],
+        id: usize,
+        now: DateTime<Utc>,
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        let idx = PlainTextTaskTracker::incomplete_task_index(tasks, id)
+            .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))?;
+        for task in tasks.iter_mut() {
+            task.stop_timer(now);
+        }
+        tasks[idx].start_timer(now);
+        if tasks[idx].status == Status::Todo {
+            tasks[idx].status = Status::InProgress;
+        }
+        Ok(())
+    }
+
+    fn stop_task_logic(
+        tasks: &mut [Task],
+        now: DateTime<Utc>,
+    ) -> Result<(), PlainTextTaskTrackerError> {
+        let mut stopped = false;
+        for task in tasks.iter_mut() {
+            stopped |= task.stop_timer(now);
+        }
+        match stopped {
+            true => Ok(()),
+            false => Err(PlainTextTaskTrackerError::NoTimerRunning),
+        }
+    }
+
+    fn is_listed(tasks: &[Task], task: &Task, filter: &ListFilter, now: DateTime<Utc>) -> bool {
+        let task_tags = task.tags.as_deref().unwrap_or_default();
+        (filter.all || task.is_open())
+            && !(filter.overdue && task.deadline.gt(&now))
+            && !(filter.ready && (!task.is_open() || PlainTextTaskTracker::is_blocked(tasks, task)))
+            && (filter.deferred || !task.is_waiting(now))
+            && filter
+                .snoozed_often
+                .is_none_or(|threshold| task.snoozed >= threshold)
+            && filter
+                .assignee
+                .as_ref()
+                .is_none_or(|assignee| task.assignee.as_ref() == Some(assignee))
+            && !(filter.waiting && task.status != Status::Waiting)
+            && filter.search.as_ref().is_none_or(|text| {
+                let text = text.to_lowercase();
+                task.name.to_lowercase().contains(&text)
+                    || task
+                        .notes
+                        .iter()
+                        .any(|note| note.to_lowercase().contains(&text))
+            })
+            && filter
+                .fields
+                .iter()
+                .all(|(name, value)| task.fields.get(name) == Some(value))
+            && filter.scheduled.is_none_or(|(from, until)| {
+                task.scheduled
+                    .is_some_and(|scheduled| from <= scheduled && scheduled < until)
+            })
+            && filter
+                .tags
+                .as_ref()
+                .is_none_or(|tags| tags.iter().all(|tag| task_tags.contains(tag)))
+    }
+
+    /// A task is blocked while any task it depends on is still incomplete
+    fn is_blocked(tasks: &[Task], task: &Task) -> bool {
+        task.depends_on.iter().any(|&dependency| {
+            tasks
+                .iter()
+                .any(|other| other.uid == Some(dependency) && other.is_open())
+        })
+    }
+
+    fn task_details(tasks: &[Task], idx: usize, now: DateTime<Utc>) -> TaskDetails<'_> {
+        let task = &tasks[idx];
+        let name_of = |uid: usize| {
+            tasks
+                .iter()
+                .find(|other| other.uid == Some(uid))
+                .map(|other| other.name.as_str())
+        };
+        TaskDetails {
+            id: task
+                .is_open()
+                .then(|| tasks[..idx].iter().filter(|other| other.is_open()).count()),
+            task,
+            local_deadline: task.deadline.with_timezone(&Local).to_rfc3339(),
+            due_in: task.is_open().then(|| format_duration(task.deadline - now)),
+            overdue: task.is_open() && task.deadline < now,
+            tracked: format_duration(task.tracked(now)),
+            blocked: PlainTextTaskTracker::is_blocked(tasks, task),
+            parent_name: task.parent.and_then(name_of),
+            depends_on_names: task
+                .depends_on
+                .iter()
+                .filter_map(|&uid| name_of(uid))
+                .collect(),
+        }
+    }
+
+    /// Every field of a task that is set, as labelled lines for `show`
+    fn detail_rows<Tz: TimeZone>(details: &TaskDetails, tz: &Tz) -> Vec<(String, String)>
+    where
+        Tz::Offset: Display,
+    {
+        let task = details.task;
+        let timestamp = |time: DateTime<Utc>| {
+            time.with_timezone(tz)
+                .format("%Y-%m-%d %H:%M %:z")
+                .to_string()
+        };
+        let mut rows = vec![("Name".to_string(), task.name.clone())];
+        let mut push = |key: &str, value: String| rows.push((key.to_string(), value));
+
+        if let Some(id) = details.id {
+            push("Id", id.to_string());
+        }
+        if let Some(uid) = task.uid {
+            push("Uid", uid.to_string());
+        }
+        push("Status", task.status.to_string());
+
+        if let Some(tags) = task.tags.as_deref().filter(|tags| !tags.is_empty()) {
+            push("Tags", tags.join(", "));
+        }
+        if let Some(priority) = task.priority {
+            push("Priority", priority.to_string());
+        }
+        if let Some(assignee) = &task.assignee {
+            push("Assignee", assignee.clone());
+        }
+        push("Due", timestamp(task.deadline));
+        push("Due (UTC)", task.export_deadline());
+        match &details.due_in {
+            Some(due_in) if details.overdue => {
+                push("Overdue by", due_in.trim_start_matches('-').into())
+            }
+            Some(due_in) => push("Time left", due_in.clone()),
+            None => (),
+        }
+        if let Some(scheduled) = task.scheduled {
+            push("Scheduled", timestamp(scheduled));
+        }
+        if let Some(wait) = task.wait {
+            push("Hidden until", timestamp(wait));
+        }
+        if let Some(created) = task.created {
+            push("Created", timestamp(created));
+        }
+        if let Some(completed) = task.completed {
+            push("Completed", timestamp(completed));
+        }
+        if let Some(parent) = details.parent_name {
+            push("Parent", parent.into());
+        }
+        if !details.depends_on_names.is_empty() {
+            let blocked = if details.blocked { " (blocked)" } else { "" };
+            push(
+                "Depends on",
+                format!("{}{blocked}", details.depends_on_names.join(", ")),
+            );
+        }
+        if let Some(estimate) = task.estimate {
+            push("Estimate", estimate.to_string());
+        }
+        if !task.work_log.is_empty() {
+            push("Tracked", details.tracked.clone());
+        }
+        if task.snoozed > 0 {
+            push("Snoozed", format!("{} times", task.snoozed));
+        }
+        if let Some((who, since)) = &task.waiting_for {
+            push("Waiting for", format!("{who} since {}", timestamp(*since)));
+        }
+        for (name, value) in &task.fields {
+            push(name, value.clone());
+        }
+        for (key, values) in [
+            ("Links", task.references()),
+            (
+                "Checklist",
+                (1..)
+                    .zip(&task.checklist)
+                    .map(|(number, item)| format!("{number}. {item}"))
+                    .collect(),
+            ),
+            ("Notes", task.notes.clone()),
+        ] {
+            for (line, value) in values.into_iter().enumerate() {
+                push(if line == 0 { key } else { "" }, value);
+            }
+        }
+        rows
+    }
+
+    /// Positions in `tasks` of the tasks `list` shows, in the order shown and paired with their
+    /// depth, and the id of every task by position
+    fn listing_logic(
+        tasks: &[Task],
+        filter: &ListFilter,
+        now: DateTime<Utc>,
+    ) -> (Vec<(usize, usize)>, Vec<Option<usize>>) {
+        let visible: Vec<bool> = tasks
+            .iter()
+            .map(|task| PlainTextTaskTracker::is_listed(tasks, task, filter, now))
+            .collect();
+
+        // Ids count every incomplete task, so filtering never changes which id a task has
+        let mut ids = vec![None; tasks.len()];
+        for (id, (idx, _)) in tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| task.is_open())
+            .enumerate()
         {
-            _ = tasks.remove(delete_idx);
+            ids[idx] = Some(id);
+        }
+
+        let order = match filter.waiting {
+            true => PlainTextTaskTracker::longest_waiting_order(tasks, &visible),
+            false => PlainTextTaskTracker::tree_order(tasks, &visible),
+        };
+        (order, ids)
+    }
+
+    /// Orders the visible tasks by how long they have been waiting, longest first, without nesting
+    fn longest_waiting_order(tasks: &[Task], visible: &[bool]) -> Vec<(usize, usize)> {
+        let mut order: Vec<usize> = (0..tasks.len()).filter(|&idx| visible[idx]).collect();
+        order.sort_by_key(|&idx| {
+            tasks[idx]
+                .waiting_for
+                .as_ref()
+                .map_or(DateTime::<Utc>::MAX_UTC, |(_, since)| *since)
+        });
+        order.into_iter().map(|idx| (idx, 0)).collect()
+    }
+
+    /// Orders the visible tasks depth first so subtasks follow their parent, paired with their depth
+    fn tree_order(tasks: &[Task], visible: &[bool]) -> Vec<(usize, usize)> {
+        let mut order = Vec::new();
+        let mut visited = vec![false; tasks.len()];
+        let has_visible_parent = |task: &Task| {
+            task.parent.is_some_and(|parent| {
+                tasks
+                    .iter()
+                    .zip(visible)
+                    .any(|(other, &shown)| shown && other.uid == Some(parent))
+            })
+        };
+
+        for (idx, task) in tasks.iter().enumerate() {
+            if visible[idx] && !has_visible_parent(task) {
+                PlainTextTaskTracker::push_subtree(
+                    tasks,
+                    visible,
+                    idx,
+                    0,
+                    &mut visited,
+                    &mut order,
+                );
+            }
+        }
+        // Tasks caught in a parent cycle have no root, so show them at the top level
+        for idx in 0..tasks.len() {
+            if visible[idx] && !visited[idx] {
+                PlainTextTaskTracker::push_subtree(
+                    tasks,
+                    visible,
+                    idx,
+                    0,
+                    &mut visited,
+                    &mut order,
+                );
+            }
+        }
+        order
+    }
+
+    fn push_subtree(
+        tasks: &[Task],
+        visible: &[bool],
+        idx: usize,
+        depth: usize,
+        visited: &mut [bool],
+        order: &mut Vec<(usize, usize)>,
+    ) {
+        if visited[idx] {
+            return;
+        }
+        visited[idx] = true;
+        order.push((idx, depth));
+        if let Some(uid) = tasks[idx].uid {
+            for (child_idx, child) in tasks.iter().enumerate() {
+                if visible[child_idx] && child.parent == Some(uid) {
+                    PlainTextTaskTracker::push_subtree(
+                        tasks,
+                        visible,
+                        child_idx,
+                        depth + 1,
+                        visited,
+                        order,
+                    );
+                }
+            }
         }
     }
+
+    /// Name indented by depth, followed by `(done/total)` when the task has subtasks
+    fn display_name(tasks: &[Task], task: &Task, depth: usize) -> String {
+        let children: Vec<&Task> = tasks
+            .iter()
+            .filter(|child| task.uid.is_some() && child.parent == task.uid)
+            .filter(|child| child.status != Status::Cancelled)
+            .collect();
+        let mut name = format!("{}{}", "  ".repeat(depth), task.name);
+        if !children.is_empty() {
+            let done = children
+                .iter()
+                .filter(|child| child.status == Status::Done)
+                .count();
+            name.push_str(&format!(" ({done}/{})", children.len()));
+        }
+        if !task.checklist.is_empty() {
+            let checked = task.checklist.iter().filter(|item| item.done).count();
+            name.push_str(&format!(" [{checked}/{}]", task.checklist.len()));
+        }
+        name
+    }
+}
+
+/// Everything `show` prints about a task, along with what is derived from the other tasks
+#[derive(Serialize)]
+struct TaskDetails<'a> {
+    /// Closed tasks have no id, only a uid
+    id: Option<usize>,
+    #[serde(flatten)]
+    task: &'a Task,
+    local_deadline: String,
+    /// Time until the deadline, negative once overdue, for open tasks only
+    due_in: Option<String>,
+    overdue: bool,
+    tracked: String,
+    blocked: bool,
+    parent_name: Option<&'a str>,
+    depends_on_names: Vec<&'a str>,
 }
 
 #[derive(Debug)]
 pub enum PlainTextTaskTrackerError {
     IO(std::io::Error),
     InvalidTask(ParseTaskError),
+    TaskNotFound(usize),
+    UidNotFound(usize),
+    DependencyCycle(usize),
+    NoTimerRunning,
+    NoLinks(usize),
+    ChecklistItemNotFound(usize, usize),
+    NothingSelected,
+    SnoozeTooFar(String),
+    Import(ImportError),
 }
 
 impl Display for PlainTextTaskTrackerError {
@@ -80,251 +982,634 @@ impl Display for PlainTextTaskTrackerError {
         match *self {
             PlainTextTaskTrackerError::IO(ref e) => e.fmt(f),
             PlainTextTaskTrackerError::InvalidTask(ref e) => e.fmt(f),
+            PlainTextTaskTrackerError::TaskNotFound(id) => write!(f, "no task with id {id}"),
+            PlainTextTaskTrackerError::UidNotFound(uid) => write!(f, "no task with uid {uid}"),
+            PlainTextTaskTrackerError::DependencyCycle(id) => {
+                write!(f, "task {id} would end up depending on itself")
+            }
+            PlainTextTaskTrackerError::NoTimerRunning => "no timer is running".fmt(f),
+            PlainTextTaskTrackerError::NoLinks(id) => write!(f, "task {id} has no links"),
+            PlainTextTaskTrackerError::ChecklistItemNotFound(id, item) => {
+                write!(f, "task {id} has no checklist item {item}")
+            }
+            PlainTextTaskTrackerError::NothingSelected => "no task matches".fmt(f),
+            PlainTextTaskTrackerError::SnoozeTooFar(ref name) => {
+                write!(f, "\"{name}\" cannot be snoozed that far")
+            }
+            PlainTextTaskTrackerError::Import(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl PlainTextTaskTrackerError {
+    /// Whether another process changed the task file while this one was changing it too, in
+    /// which case nothing was changed and the change can be made again
+    pub fn is_conflict(&self) -> bool {
+        matches!(*self, PlainTextTaskTrackerError::IO(ref e) if Conflict::is(e))
+    }
+}
+
+impl From<std::io::Error> for PlainTextTaskTrackerError {
+    fn from(value: std::io::Error) -> Self {
+        PlainTextTaskTrackerError::IO(value)
+    }
+}
+
+impl From<ParseTaskError> for PlainTextTaskTrackerError {
+    fn from(value: ParseTaskError) -> Self {
+        PlainTextTaskTrackerError::InvalidTask(value)
+    }
+}
+
+impl From<ImportError> for PlainTextTaskTrackerError {
+    fn from(value: ImportError) -> Self {
+        PlainTextTaskTrackerError::Import(value)
+    }
+}
+
+impl Error for PlainTextTaskTrackerError {}
+
+impl<F: TaskFileFormat> TaskTracker for PlainTextTaskTracker<F> {
+    type Err = PlainTextTaskTrackerError;
+
+    fn add_task(&self, new_task: NewTask) -> Result<usize, Self::Err> {
+        let mut tasks = self.load_or_default()?;
+        let uids_assigned = PlainTextTaskTracker::assign_missing_uids(&mut tasks);
+
+        let mut task = Task::new(new_task.name, new_task.tags, new_task.deadline);
+        // Archived tasks keep their uids, which must not be handed out again
+        let next_uid = PlainTextTaskTracker::next_uid(&tasks)
+            .max(PlainTextTaskTracker::next_uid(&self.load_archive()?));
+        task.uid = Some(next_uid);
+        task.priority = new_task.priority;
+        task.estimate = new_task.estimate;
+        task.scheduled = new_task.scheduled;
+        task.wait = new_task.wait;
+        task.assignee = new_task.assignee;
+        task.fields = new_task.fields.into_iter().collect();
+        task.links = new_task.links;
+        task.notes = new_task.notes;
+        task.created = Some(Utc::now());
+        if let Some(parent_id) = new_task.parent {
+            let parent_idx = PlainTextTaskTracker::incomplete_task_index(&tasks, parent_id)
+                .ok_or(PlainTextTaskTrackerError::TaskNotFound(parent_id))?;
+            task.parent = tasks[parent_idx].uid;
+        }
+        if let Some(depends_on) = new_task.depends_on {
+            task.depends_on = PlainTextTaskTracker::resolve_uids(&tasks, &depends_on)?;
+        }
+
+        if uids_assigned || !F::APPENDABLE {
+            tasks.push(task);
+            self.store(&tasks)?;
+            return Ok(next_uid);
+        }
+
+        let mut contents = Vec::new();
+        F::append_task(&mut contents, &task)?;
+        self.storage.append(TaskFile::Tasks, &contents)?;
+        Ok(next_uid)
+    }
+
+    fn complete_task(
+        &mut self,
+        selection: Selection,
+        cascade: bool,
+        yes: bool,
+    ) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        let indices = PlainTextTaskTracker::select_logic(&tasks, &selection, Utc::now())?;
+        if !PlainTextTaskTracker::confirm_selection(&tasks, &indices, &selection, "complete", yes) {
+            return Ok(());
+        }
+
+        let open_subtasks = indices
+            .iter()
+            .flat_map(|&idx| PlainTextTaskTracker::descendant_indices(&tasks, idx))
+            .filter(|&descendant| tasks[descendant].is_open() && !indices.contains(&descendant))
+            .count();
+        let cascade = cascade
+            || (!yes
+                && open_subtasks > 0
+                && confirm(&format!(
+                    "{open_subtasks} incomplete subtask(s) would be left open. Complete them as well?"
+                )));
+        PlainTextTaskTracker::complete_task_logic(&mut tasks, &indices, cascade, Utc::now());
+
+        self.store(&tasks)
+    }
+
+    fn delete_task(&mut self, selection: Selection, yes: bool) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        let indices = PlainTextTaskTracker::select_logic(&tasks, &selection, Utc::now())?;
+        if !PlainTextTaskTracker::confirm_selection(&tasks, &indices, &selection, "delete", yes) {
+            return Ok(());
+        }
+        PlainTextTaskTracker::delete_task_logic(&mut tasks, &indices);
+        self.store(&tasks)
+    }
+
+    fn edit_task(
+        &mut self,
+        selection: Selection,
+        edit: TaskEdit,
+        yes: bool,
+    ) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        let indices = PlainTextTaskTracker::select_logic(&tasks, &selection, Utc::now())?;
+        if !PlainTextTaskTracker::confirm_selection(&tasks, &indices, &selection, "edit", yes) {
+            return Ok(());
+        }
+        PlainTextTaskTracker::edit_task_logic(&mut tasks, &indices, &edit);
+        self.store(&tasks)
+    }
+
+    fn set_status(&mut self, task: TaskRef, status: Status) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        let now = Utc::now();
+        if let TaskRef::Uid(uid) = task
+            && !tasks.iter().any(|task| task.uid == Some(uid))
+        {
+            let mut archive = self.load_archive()?;
+            PlainTextTaskTracker::set_status_logic(&mut archive, task, status, now)?;
+            // A reopened task moves back to the task file, written first so it is never lost
+            if status.is_open() {
+                let idx = PlainTextTaskTracker::task_index(&archive, task)?;
+                tasks.push(archive.remove(idx));
+                tasks.sort_by_key(|task| task.deadline);
+                self.store_archiving(&tasks, &[])?;
+            }
+            return self.write(TaskFile::Archive, &archive.iter().collect::<Vec<_>>());
+        }
+        PlainTextTaskTracker::set_status_logic(&mut tasks, task, status, now)?;
+        self.store(&tasks)
+    }
+
+    fn add_checklist_item(&mut self, id: usize, text: String) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        PlainTextTaskTracker::add_checklist_item_logic(&mut tasks, id, text)?;
+        self.store(&tasks)
+    }
+
+    fn check_item(&mut self, id: usize, item: usize, done: bool) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        PlainTextTaskTracker::check_item_logic(&mut tasks, id, item, done)?;
+        self.store(&tasks)
+    }
+
+    fn show_task(&self, task: TaskRef, format: OutputFormat) -> Result<(), Self::Err> {
+        // Archived tasks are all closed, so the open tasks keep their ids among them
+        let tasks = self.load_with_archive()?;
+        let idx = PlainTextTaskTracker::task_index(&tasks, task)?;
+        let details = PlainTextTaskTracker::task_details(&tasks, idx, Utc::now());
+
+        match format {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&details).map_err(std::io::Error::from)?
+            ),
+            OutputFormat::Text => {
+                let rows = PlainTextTaskTracker::detail_rows(&details, &Local);
+                let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+                for (key, value) in rows {
+                    println!("{key:<width$}  {value}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_links(&mut self, id: usize, links: Vec<String>) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        PlainTextTaskTracker::add_links_logic(&mut tasks, id, links)?;
+        self.store(&tasks)
+    }
+
+    fn open_task(&self, id: usize, print: bool) -> Result<(), Self::Err> {
+        let tasks = self.load()?;
+        let idx = PlainTextTaskTracker::incomplete_task_index(&tasks, id)
+            .ok_or(PlainTextTaskTrackerError::TaskNotFound(id))?;
+        let references = tasks[idx].references();
+        if references.is_empty() {
+            return Err(PlainTextTaskTrackerError::NoLinks(id));
+        }
+        for reference in references {
+            // Without a working opener the reference is printed so it can still be followed
+            if print || !open_external(&reference).unwrap_or(false) {
+                println!("{reference}");
+            }
+        }
+        Ok(())
+    }
+
+    fn wait_for(&mut self, id: usize, who: String) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        PlainTextTaskTracker::wait_for_logic(&mut tasks, id, who, Utc::now())?;
+        self.store(&tasks)
+    }
+
+    fn snooze_task(
+        &mut self,
+        selection: Selection,
+        snooze: Snooze,
+        yes: bool,
+    ) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        let indices = PlainTextTaskTracker::select_logic(&tasks, &selection, Utc::now())?;
+        if !PlainTextTaskTracker::confirm_selection(&tasks, &indices, &selection, "snooze", yes) {
+            return Ok(());
         }
+        PlainTextTaskTracker::snooze_task_logic(&mut tasks, &indices, snooze)?;
+        self.store(&tasks)
     }
-}
 
-impl From<std::io::Error> for PlainTextTaskTrackerError {
-    fn from(value: std::io::Error) -> Self {
-        PlainTextTaskTrackerError::IO(value)
+    fn add_dependencies(&mut self, id: usize, depends_on: Vec<usize>) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        PlainTextTaskTracker::assign_missing_uids(&mut tasks);
+        PlainTextTaskTracker::add_dependencies_logic(&mut tasks, id, &depends_on)?;
+        self.store(&tasks)
     }
-}
 
-impl From<ParseTaskError> for PlainTextTaskTrackerError {
-    fn from(value: ParseTaskError) -> Self {
-        PlainTextTaskTrackerError::InvalidTask(value)
-    }
-}
+    fn list_task(&self, mut filter: ListFilter) -> Result<(), Self::Err> {
+        let tasks = match filter.archived {
+            true => self.load_archive()?,
+            false => self.load()?,
+        };
+        // Archived tasks are all closed, so they are listed like `--all` without ids
+        filter.all |= filter.archived;
+        let now = Utc::now();
+        let (order, ids) = PlainTextTaskTracker::listing_logic(&tasks, &filter, now);
+        let show_assignee = order.iter().any(|&(idx, _)| tasks[idx].assignee.is_some());
+
+        let rows: Vec<TableRow> = order
+            .into_iter()
+            .map(|(idx, depth)| {
+                let task = &tasks[idx];
+                // Closed tasks have no id, so `--all` shows uids, which `--uid` options take
+                let id = match filter.all {
+                    true => task.uid,
+                    false => ids[idx],
+                };
+                let mut cells = vec![id.map(|id| id.to_string()).unwrap_or_default()];
+                cells.push(PlainTextTaskTracker::display_name(&tasks, task, depth));
+                cells.push(task.status.to_string());
+                cells.push(task.tags.as_deref().unwrap_or_default().join(", "));
+                cells.push(task.local_deadline());
+                if show_assignee {
+                    cells.push(task.assignee.clone().unwrap_or_default());
+                }
+                if filter.snoozed_often.is_some() {
+                    cells.push(task.snoozed.to_string());
+                }
+                for column in &filter.columns {
+                    cells.push(task.fields.get(column).cloned().unwrap_or_default());
+                }
+                if filter.waiting {
+                    let (who, since) = task
+                        .waiting_for
+                        .as_ref()
+                        .map(|(who, since)| (who.clone(), format_duration(now - *since)))
+                        .unwrap_or_default();
+                    cells.push(who);
+                    cells.push(since);
+                }
 
-impl Error for PlainTextTaskTrackerError {}
+                let effects = if task.status == Status::Done {
+                    vec![TextEffect::StrikeThrough, TextEffect::Green]
+                } else if task.status == Status::Cancelled {
+                    vec![TextEffect::StrikeThrough, TextEffect::Dim]
+                } else if PlainTextTaskTracker::is_blocked(&tasks, task) {
+                    vec![TextEffect::Yellow]
+                } else if task.deadline.lt(&now) {
+                    vec![TextEffect::Red]
+                } else {
+                    vec![]
+                };
+                TableRow { cells, effects }
+            })
+            .collect();
 
-impl TaskTracker for PlainTextTaskTracker {
-    type Err = PlainTextTaskTrackerError;
+        let mut headers = vec![if filter.all { "Uid" } else { "Id" }];
+        headers.extend(["Name", "Status", "Tags", "Due"]);
+        if show_assignee {
+            headers.push("Assignee");
+        }
+        if filter.snoozed_often.is_some() {
+            headers.push("Snoozed");
+        }
+        headers.extend(filter.columns.iter().map(String::as_str));
+        if filter.waiting {
+            headers.extend(["Waiting for", "Waited"]);
+        }
+        print_table(&headers, &rows);
 
-    fn add_task(
-        &self,
-        name: String,
-        tags: Option<Vec<String>>,
-        deadline: DateTime<Utc>,
-    ) -> Result<(), Self::Err> {
-        let task = Task::new(name, tags, deadline);
-        let file = OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(&self.file_path)?;
-        let mut writer = BufWriter::new(file);
-        PlainTextTaskTracker::add_task_logic(&mut writer, task)?;
         Ok(())
     }
 
-    fn complete_task(&mut self, id: usize) -> Result<(), Self::Err> {
-        let file = OpenOptions::new().read(true).open(&self.file_path)?;
-        let reader = BufReader::new(file);
-        let mut tasks = PlainTextTaskTracker::read_tasks_from_file(reader)?;
-        PlainTextTaskTracker::complete_task_logic(&mut tasks, id);
+    fn listed_tasks(&self, mut filter: ListFilter) -> Result<Vec<ListedTask>, Self::Err> {
+        let mut tasks = match filter.archived {
+            true => self.load_archive()?,
+            false => self.load()?,
+        };
+        filter.all |= filter.archived;
+        let (order, ids) = PlainTextTaskTracker::listing_logic(&tasks, &filter, Utc::now());
+        // Every task is listed at most once, so each can be moved out rather than cloned
+        Ok(order
+            .into_iter()
+            .map(|(idx, _)| ListedTask {
+                id: ids[idx],
+                task: std::mem::take(&mut tasks[idx]),
+            })
+            .collect())
+    }
 
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&self.file_path)?;
-        let mut writer = BufWriter::new(file);
-        PlainTextTaskTracker::write_tasks_to_file(&mut writer, &tasks)?;
+    fn archive_tasks(&mut self, older_than: Option<TimeDelta>) -> Result<(), Self::Err> {
+        let tasks = self.load()?;
+        let cutoff = Utc::now() - older_than.unwrap_or_default();
+        let archived = PlainTextTaskTracker::archive_logic(&tasks, cutoff);
+        self.store_archiving(&tasks, &archived)?;
+        println!(
+            "Archived {} task(s) to {}",
+            archived.len(),
+            self.archive_path().display()
+        );
+        Ok(())
+    }
 
+    fn export_tasks(
+        &mut self,
+        format: ExchangeFormat,
+        options: ExportOptions,
+    ) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        // Exported tasks are recognized by their UUID when imported again, and refer to each
+        // other by uid
+        let assigned = PlainTextTaskTracker::assign_missing_uids(&mut tasks);
+        if PlainTextTaskTracker::assign_missing_uuids(&mut tasks) || assigned {
+            self.store(&tasks)?;
+        }
+        print!(
+            "{}",
+            exchange::export(format, &tasks, &options, &Local, Utc::now())
+        );
         Ok(())
     }
 
-    fn delete_task(&mut self, id: usize) -> Result<(), Self::Err> {
-        let file = OpenOptions::new().read(true).open(&self.file_path)?;
-        let reader = BufReader::new(file);
-        let mut tasks = PlainTextTaskTracker::read_tasks_from_file(reader)?;
-        PlainTextTaskTracker::delete_task_logic(&mut tasks, id);
+    fn import_tasks(
+        &mut self,
+        format: ExchangeFormat,
+        input: &str,
+        options: ImportOptions,
+    ) -> Result<(), Self::Err> {
+        let exchange::Imported {
+            tasks: imported,
+            rejected,
+        } = exchange::import(format, input, &options, &Local, Utc::now())?;
+        let total = imported.len();
+        let mut tasks = self.load_or_default()?;
+        PlainTextTaskTracker::assign_missing_uids(&mut tasks);
+        let added = PlainTextTaskTracker::import_logic(&mut tasks, &self.load_archive()?, imported);
+        self.store(&tasks)?;
+        match total - added {
+            0 => println!("Imported {added} task(s)"),
+            skipped => println!("Imported {added} task(s), skipped {skipped} already present"),
+        }
+        for e in &rejected {
+            eprintln!("Could not import {e}");
+        }
+        Ok(())
+    }
 
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&self.file_path)?;
-        let mut writer = BufWriter::new(file);
-        PlainTextTaskTracker::write_tasks_to_file(&mut writer, &tasks)?;
+    fn start_task(&mut self, id: usize) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        PlainTextTaskTracker::start_task_logic(&mut tasks, id, Utc::now())?;
+        self.store(&tasks)
+    }
 
-        Ok(())
+    fn stop_task(&mut self) -> Result<(), Self::Err> {
+        let mut tasks = self.load()?;
+        PlainTextTaskTracker::stop_task_logic(&mut tasks, Utc::now())?;
+        self.store(&tasks)
     }
 
-    fn list_task(
+    fn time_report(
         &self,
-        incomplete: bool,
-        overdue: bool,
-        tags: Option<Vec<String>>,
+        since: Option<DateTime<Utc>>,
+        grouping: ReportGrouping,
     ) -> Result<(), Self::Err> {
-        let file = OpenOptions::new().read(true).open(&self.file_path)?;
-        let reader = BufReader::new(file);
-        let tasks = PlainTextTaskTracker::read_tasks_from_file(reader)?;
-
-        let tasks = match incomplete {
-            true => tasks,
-            false => tasks.into_iter().filter(|task| !task.complete).collect(),
+        let tasks = self.load_with_archive()?;
+        let totals = time_by(&tasks, since, Utc::now(), grouping, &Local);
+        let total: TimeDelta = totals.iter().map(|(_, worked)| *worked).sum();
+
+        let mut rows: Vec<TableRow> = totals
+            .into_iter()
+            .map(|(key, worked)| TableRow {
+                cells: vec![key, format_hours(worked)],
+                effects: vec![],
+            })
+            .collect();
+        if grouping != ReportGrouping::Tag {
+            rows.push(TableRow {
+                cells: vec!["Total".into(), format_hours(total)],
+                effects: vec![],
+            });
+        }
+        let header = match grouping {
+            ReportGrouping::Task => "Task",
+            ReportGrouping::Tag => "Tag",
+            ReportGrouping::Day => "Day",
         };
+        print_table(&[header, "Hours"], &rows);
 
-        const COLUMN_PADDING: usize = 4;
-        const DEADLINE_LENGTH: usize = 16;
-        let max_id_length = (1 + tasks.len().checked_ilog10().unwrap_or(1) as usize).max(2);
-        let mut max_name_length = 4;
-        let mut max_tags_length = 4;
+        Ok(())
+    }
 
-        for task in &tasks {
-            max_name_length = max_name_length.max(task.name.len());
-            max_tags_length =
-                max_tags_length.max(task.tags.as_deref().unwrap_or_default().join(", ").len());
-        }
+    fn estimate_report(
+        &self,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<(), Self::Err> {
+        let tasks = self.load_with_archive()?;
+        let rows: Vec<TableRow> = estimates_by_tag(&tasks, since, until, Utc::now())
+            .into_iter()
+            .map(|summary| {
+                let difference = summary.actual - summary.estimated;
+                let effects = match difference > TimeDelta::zero() {
+                    true => vec![TextEffect::Red],
+                    false => vec![],
+                };
+                TableRow {
+                    cells: vec![
+                        summary.tag,
+                        format_hours(summary.estimated),
+                        format_hours(summary.actual),
+                        format!("{:+.2}", difference.num_seconds() as f64 / 3600.0),
+                        summary.points.to_string(),
+                        match summary.points {
+                            0 => String::new(),
+                            points => format!(
+                                "{:.2}",
+                                summary.point_actual.num_seconds() as f64 / 3600.0 / points as f64
+                            ),
+                        },
+                        format_hours(summary.cancelled),
+                    ],
+                    effects,
+                }
+            })
+            .collect();
+        print_table(
+            &[
+                "Tag",
+                "Estimated",
+                "Actual",
+                "Difference",
+                "Points",
+                "Hours/point",
+                "Cancelled",
+            ],
+            &rows,
+        );
 
-        if incomplete {
-            println!(
-                "Name{}Tags{}Due",
-                " ".repeat(max_name_length - "Name".len() + COLUMN_PADDING),
-                " ".repeat(max_tags_length - "Tags".len() + COLUMN_PADDING),
-            );
+        Ok(())
+    }
 
-            println!(
-                "{}",
-                "-".repeat(
-                    max_name_length + max_tags_length + DEADLINE_LENGTH + (COLUMN_PADDING * 2)
-                )
-            );
-            for task in tasks.iter() {
-                if overdue && task.deadline.gt(&Utc::now()) {
-                    continue;
-                }
-                if let Some(tags) = &tags {
-                    if !tags
-                        .iter()
-                        .all(|tag| task.tags.as_deref().unwrap_or_default().contains(tag))
-                    {
-                        continue;
-                    }
-                }
+    fn load_report(&self, available: TimeDelta) -> Result<(), Self::Err> {
+        let tasks = self.load()?;
+        let now = Utc::now();
+        let week_end = week_end(now, &Local);
 
-                let deadline_str = task.local_deadline();
-                let tags_display = task.tags.as_deref().unwrap_or_default().join(", ");
-                match (&task.complete, &task.deadline.lt(&Utc::now())) {
-                    (&true, _) => {
-                        println!(
-                            "{}{}{}{}{}",
-                            add_text_effect(
-                                &add_text_effect(&task.name, TextEffect::StrikeThrough),
-                                TextEffect::Green
-                            ),
-                            " ".repeat(max_name_length - task.name.len() + COLUMN_PADDING),
-                            add_text_effect(
-                                &add_text_effect(&tags_display, TextEffect::StrikeThrough),
-                                TextEffect::Green
-                            ),
-                            " ".repeat(max_tags_length - tags_display.len() + COLUMN_PADDING),
-                            add_text_effect(
-                                &add_text_effect(&deadline_str, TextEffect::StrikeThrough),
-                                TextEffect::Green
-                            )
-                        );
-                    }
-                    (&false, &true) => {
-                        println!(
-                            "{}{}{}{}{}",
-                            add_text_effect(&task.name, TextEffect::Red),
-                            " ".repeat(max_name_length - task.name.len() + COLUMN_PADDING),
-                            add_text_effect(&tags_display, TextEffect::Red),
-                            " ".repeat(max_tags_length - tags_display.len() + COLUMN_PADDING),
-                            add_text_effect(&deadline_str, TextEffect::Red)
-                        );
-                    }
-                    (&false, &false) => {
-                        println!(
-                            "{}{}{}{}{}",
-                            &task.name,
-                            " ".repeat(max_name_length - task.name.len() + COLUMN_PADDING),
-                            &tags_display,
-                            " ".repeat(max_tags_length - tags_display.len() + COLUMN_PADDING),
-                            &deadline_str,
-                        );
-                    }
+        let mut remaining_total = TimeDelta::zero();
+        let mut unestimated = 0;
+        let rows: Vec<TableRow> = tasks
+            .iter()
+            .filter(|task| task.is_open())
+            .enumerate()
+            .filter(|(_, task)| task.deadline < week_end)
+            .map(|(id, task)| {
+                let remaining = task.remaining_estimate(now);
+                match remaining {
+                    Some(remaining) => remaining_total += remaining,
+                    None => unestimated += 1,
                 }
-            }
-        } else {
-            println!(
-                "Id{}Name{}Tags{}Due",
-                " ".repeat(max_id_length - "Id".len() + COLUMN_PADDING),
-                " ".repeat(max_name_length - "Name".len() + COLUMN_PADDING),
-                " ".repeat(max_tags_length - "Tags".len() + COLUMN_PADDING),
-            );
+                TableRow {
+                    cells: vec![
+                        id.to_string(),
+                        task.name.clone(),
+                        task.local_deadline(),
+                        task.estimate
+                            .map(|estimate| estimate.to_string())
+                            .unwrap_or_default(),
+                        format_duration(task.tracked(now)),
+                        remaining.map(format_duration).unwrap_or_default(),
+                    ],
+                    effects: vec![],
+                }
+            })
+            .collect();
+        print_table(
+            &["Id", "Name", "Due", "Estimate", "Tracked", "Remaining"],
+            &rows,
+        );
 
-            println!(
-                "{}",
-                "-".repeat(
-                    max_name_length
-                        + max_tags_length
-                        + max_id_length
-                        + DEADLINE_LENGTH
-                        + (COLUMN_PADDING * 3)
-                )
-            );
+        let summary = format!(
+            "{} remaining of {} available",
+            format_duration(remaining_total),
+            format_duration(available)
+        );
+        match remaining_total > available {
+            true => println!("\n{}", add_text_effect(&summary, TextEffect::Red)),
+            false => println!("\n{summary}"),
+        }
+        if unestimated > 0 {
+            println!("{unestimated} task(s) due this week have no time estimate");
+        }
 
-            for (id, task) in tasks.iter().enumerate() {
-                if overdue && task.deadline.gt(&Utc::now()) {
-                    continue;
-                }
+        Ok(())
+    }
 
-                if let Some(tags) = &tags {
-                    if !tags
-                        .iter()
-                        .all(|tag| task.tags.as_deref().unwrap_or_default().contains(tag))
-                    {
-                        continue;
-                    }
-                }
+    fn next_task(&self, count: usize, weights: &UrgencyWeights) -> Result<(), Self::Err> {
+        let tasks = self.load()?;
+        let now = Utc::now();
 
-                let deadline_str = task.local_deadline();
-                let tags_display = task.tags.as_deref().unwrap_or_default().join(", ");
-                match &task.deadline.lt(&Utc::now()) {
-                    true => {
-                        println!(
-                            "{}{}{}{}{}{}{}",
-                            add_text_effect(&id.to_string(), TextEffect::Red),
-                            " ".repeat(
-                                max_id_length - (id.checked_ilog10().unwrap_or(0) as usize + 1)
-                                    + COLUMN_PADDING
-                            ),
-                            add_text_effect(&task.name, TextEffect::Red),
-                            " ".repeat(max_name_length - task.name.len() + COLUMN_PADDING),
-                            add_text_effect(&tags_display, TextEffect::Red),
-                            " ".repeat(max_tags_length - tags_display.len() + COLUMN_PADDING),
-                            add_text_effect(&deadline_str, TextEffect::Red),
-                        );
-                    }
-                    false => {
-                        println!(
-                            "{}{}{}{}{}{}{}",
-                            &id.to_string(),
-                            " ".repeat(
-                                max_id_length - (id.checked_ilog10().unwrap_or(0) as usize + 1)
-                                    + COLUMN_PADDING
-                            ),
-                            &task.name,
-                            " ".repeat(max_name_length - task.name.len() + COLUMN_PADDING),
-                            &tags_display,
-                            " ".repeat(max_tags_length - tags_display.len() + COLUMN_PADDING),
-                            deadline_str
-                        );
-                    }
+        let mut ranked = tasks
+            .iter()
+            .filter(|task| task.is_open())
+            .enumerate()
+            .filter(|(_, task)| !task.is_waiting(now))
+            .map(|(id, task)| {
+                let blocked = PlainTextTaskTracker::is_blocked(&tasks, task);
+                let factors = urgency(task, blocked, now, weights);
+                let score = factors.iter().map(|(_, score)| score).sum::<f64>();
+                (id, task, score, factors)
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|(_, _, score_a, _), (_, _, score_b, _)| score_b.total_cmp(score_a));
+
+        let rows: Vec<TableRow> = ranked
+            .into_iter()
+            .take(count)
+            .map(|(id, task, score, factors)| {
+                let why: Vec<String> = factors
+                    .iter()
+                    .map(|(factor, score)| format!("{factor} {score:+.1}"))
+                    .collect();
+                let effects = if PlainTextTaskTracker::is_blocked(&tasks, task) {
+                    vec![TextEffect::Yellow]
+                } else if task.deadline.lt(&now) {
+                    vec![TextEffect::Red]
+                } else {
+                    vec![]
+                };
+                TableRow {
+                    cells: vec![
+                        id.to_string(),
+                        format!("{score:.1}"),
+                        task.name.clone(),
+                        task.local_deadline(),
+                        why.join(", "),
+                    ],
+                    effects,
                 }
-            }
-        }
+            })
+            .collect();
+        print_table(&["Id", "Score", "Name", "Due", "Why"], &rows);
 
         Ok(())
     }
+
+    fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
+
+    fn exists(&self) -> Result<bool, Self::Err> {
+        Ok(self.storage.exists(TaskFile::Tasks)? || self.storage.exists(TaskFile::Archive)?)
+    }
+
+    fn read_all(&self) -> Result<TaskSet, Self::Err> {
+        Ok(TaskSet {
+            tasks: self.load()?,
+            archived: self.load_archive()?,
+        })
+    }
+
+    fn write_all(&mut self, set: &TaskSet) -> Result<(), Self::Err> {
+        self.store_archiving(&set.tasks, &[])?;
+        match set.archived.is_empty() {
+            true => Ok(self.storage.remove(TaskFile::Archive)?),
+            false => self.write(TaskFile::Archive, &set.archived.iter().collect::<Vec<_>>()),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::task_tracker::selection::{Condition, IdRange};
+    use crate::task_tracker::task::{Priority, WorkInterval};
     use chrono::DateTime;
-    use std::io::Cursor;
+    use std::io::{BufReader, Cursor};
 
     #[test]
     fn plain_text_task_tracker_parse_file_one_task() {
@@ -337,7 +1622,8 @@ mod test {
             deadline: DateTime::parse_from_rfc3339("2025-03-17T22:00:00+00:00")
                 .unwrap()
                 .to_utc(),
-            complete: false,
+            status: Status::Todo,
+            ..Default::default()
         }]);
         assert_eq!(expected_task, actual_task);
     }
@@ -354,7 +1640,8 @@ mod test {
                 deadline: DateTime::parse_from_rfc3339("2025-03-17T22:00:00+00:00")
                     .unwrap()
                     .to_utc(),
-                complete: false,
+                status: Status::Todo,
+                ..Default::default()
             },
             Task {
                 name: "Task 2".into(),
@@ -362,7 +1649,8 @@ mod test {
                 deadline: DateTime::parse_from_rfc3339("2025-03-19T22:00:00+00:00")
                     .unwrap()
                     .to_utc(),
-                complete: true,
+                status: Status::Done,
+                ..Default::default()
             },
         ]);
         assert_eq!(expected_task, actual_task);
@@ -382,42 +1670,47 @@ mod test {
             Task {
                 name: "Task 4".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: false,
+                status: Status::Todo,
                 deadline: DateTime::parse_from_rfc3339("2025-01-10T16:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
             Task {
                 name: "Task 5".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: true,
+                status: Status::Done,
                 deadline: DateTime::parse_from_rfc3339("2025-02-20T03:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
             Task {
                 name: "Task 1".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: false,
+                status: Status::Todo,
                 deadline: DateTime::parse_from_rfc3339("2025-03-01T22:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
             Task {
                 name: "Task 3".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: false,
+                status: Status::Todo,
                 deadline: DateTime::parse_from_rfc3339("2025-03-02T13:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
             Task {
                 name: "Task 2".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: false,
+                status: Status::Todo,
                 deadline: DateTime::parse_from_rfc3339("2025-03-02T13:01:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
         ];
         assert_eq!(expected_tasks, actual_tasks.unwrap());
@@ -437,42 +1730,47 @@ mod test {
             Task {
                 name: "Task 5".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: true,
+                status: Status::Done,
                 deadline: DateTime::parse_from_rfc3339("2025-02-20T03:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
             Task {
                 name: "Task 1".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: false,
+                status: Status::Todo,
                 deadline: DateTime::parse_from_rfc3339("2025-03-01T22:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
             Task {
                 name: "Task 4".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: false,
+                status: Status::Todo,
                 deadline: DateTime::parse_from_rfc3339("2025-03-01T22:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
             Task {
                 name: "Task 2".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: false,
+                status: Status::Todo,
                 deadline: DateTime::parse_from_rfc3339("2025-03-02T13:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
             Task {
                 name: "Task 3".into(),
                 tags: Some(vec!["project".into(), "ugh".into()]),
-                complete: false,
+                status: Status::Todo,
                 deadline: DateTime::parse_from_rfc3339("2025-03-02T13:00:00+00:00")
                     .unwrap()
                     .to_utc(),
+                ..Default::default()
             },
         ];
         assert_eq!(expected_tasks, actual_tasks.unwrap());
@@ -496,7 +1794,7 @@ mod test {
 
         let actual_output = cursor.get_ref();
 
-        let expected_output = b"| Task 3 | workin' | false | 2025-03-17T22:00:00+00:00 |\n";
+        let expected_output = b"| Task 3 | workin' | todo | 2025-03-17T22:00:00+00:00 |\n";
         assert_eq!(actual_output, expected_output);
     }
 
@@ -534,9 +1832,9 @@ mod test {
 
         let actual_output = cursor.get_ref();
 
-        let expected_output = br#"| Task 1 |  | false | 2025-03-17T22:00:00+00:00 |
-| Task 2 | sleepin' | false | 2025-03-17T22:00:00+00:00 |
-| Task 3 | workin' | false | 2025-03-17T22:00:00+00:00 |
+        let expected_output = br#"| Task 1 |  | todo | 2025-03-17T22:00:00+00:00 |
+| Task 2 | sleepin' | todo | 2025-03-17T22:00:00+00:00 |
+| Task 3 | workin' | todo | 2025-03-17T22:00:00+00:00 |
 "#;
         assert_eq!(actual_output, expected_output);
     }
@@ -554,12 +1852,12 @@ mod test {
                 .to_utc(),
         );
 
-        let res = PlainTextTaskTracker::add_task_logic(&mut cursor, new_task);
+        let res = PlainTextTaskTracker::add_task_logic(&mut cursor, &new_task);
         assert!(res.is_ok());
 
         let actual_output = cursor.get_ref();
 
-        let expected_output = b"| Task 3 | workin' | false | 2025-03-17T22:00:00+00:00 |\n";
+        let expected_output = b"| Task 3 | workin' | todo | 2025-03-17T22:00:00+00:00 |\n";
         assert_eq!(actual_output, expected_output);
     }
 
@@ -577,12 +1875,12 @@ mod test {
                 .to_utc(),
         );
 
-        let res = PlainTextTaskTracker::add_task_logic(&mut cursor, new_task);
+        let res = PlainTextTaskTracker::add_task_logic(&mut cursor, &new_task);
         assert!(res.is_ok());
 
         let actual_output = cursor.get_ref();
 
-        let expected_output = b"| Task 1 | ugh | false | 2025-03-17T22:00:00+00:00 |\n| Task 2 | project, time | true | 2025-03-19T22:00:00+00:00 |\n| Task 3 | workin' | false | 2025-03-17T22:00:00+00:00 |\n";
+        let expected_output = b"| Task 1 | ugh | false | 2025-03-17T22:00:00+00:00 |\n| Task 2 | project, time | true | 2025-03-19T22:00:00+00:00 |\n| Task 3 | workin' | todo | 2025-03-17T22:00:00+00:00 |\n";
         assert_eq!(actual_output, expected_output);
     }
 
@@ -604,8 +1902,7 @@ mod test {
                     .to_utc(),
             ),
         ];
-        let id = 1;
-        PlainTextTaskTracker::complete_task_logic(&mut tasks, id);
+        PlainTextTaskTracker::complete_task_logic(&mut tasks, &[1], false, now());
 
         let expected_tasks = [
             Task::new(
@@ -621,7 +1918,9 @@ mod test {
                 deadline: DateTime::parse_from_rfc3339("2025-03-18T22:00:00+00:00")
                     .unwrap()
                     .to_utc(),
-                complete: true,
+                status: Status::Done,
+                completed: Some(now()),
+                ..Default::default()
             },
         ];
 
@@ -630,7 +1929,7 @@ mod test {
 
     #[test]
     fn plain_text_task_tracker_complete_task_id_does_not_exist() {
-        let mut tasks = vec![
+        let tasks = vec![
             Task::new(
                 "Task 0".into(),
                 Some(vec!["ugh".into(), "project".into()]),
@@ -646,8 +1945,14 @@ mod test {
                     .to_utc(),
             ),
         ];
-        let id = 100;
-        PlainTextTaskTracker::complete_task_logic(&mut tasks, id);
+        let selection = Selection {
+            ids: vec![IdRange(100..=100)],
+            ..Default::default()
+        };
+        assert!(matches!(
+            PlainTextTaskTracker::select_logic(&tasks, &selection, now()),
+            Err(PlainTextTaskTrackerError::TaskNotFound(100))
+        ));
 
         let expected_tasks = [
             Task::new(
@@ -687,8 +1992,7 @@ mod test {
                     .to_utc(),
             ),
         ];
-        let id = 1;
-        PlainTextTaskTracker::delete_task_logic(&mut tasks, id);
+        PlainTextTaskTracker::delete_task_logic(&mut tasks, &[1]);
 
         let expected_tasks = [Task::new(
             "Task 0".into(),
@@ -703,7 +2007,7 @@ mod test {
 
     #[test]
     fn plain_text_task_tracker_delete_task_id_does_not_exist() {
-        let mut tasks = vec![
+        let tasks = vec![
             Task::new(
                 "Task 0".into(),
                 Some(vec!["ugh".into(), "project".into()]),
@@ -719,8 +2023,14 @@ mod test {
                     .to_utc(),
             ),
         ];
-        let id = 100;
-        PlainTextTaskTracker::delete_task_logic(&mut tasks, id);
+        let selection = Selection {
+            ids: vec![IdRange(100..=100)],
+            ..Default::default()
+        };
+        assert!(matches!(
+            PlainTextTaskTracker::select_logic(&tasks, &selection, now()),
+            Err(PlainTextTaskTrackerError::TaskNotFound(100))
+        ));
 
         let expected_tasks = [
             Task::new(
@@ -741,4 +2051,638 @@ mod test {
 
         assert_eq!(tasks, expected_tasks)
     }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-03-10T09:00:00+00:00")
+            .unwrap()
+            .to_utc()
+    }
+
+    fn subtask_tree() -> Vec<Task> {
+        let deadline = DateTime::parse_from_rfc3339("2025-03-17T22:00:00+00:00")
+            .unwrap()
+            .to_utc();
+        vec![
+            Task {
+                name: "Child".into(),
+                deadline,
+                uid: Some(2),
+                parent: Some(1),
+                ..Default::default()
+            },
+            Task {
+                name: "Parent".into(),
+                deadline,
+                uid: Some(1),
+                ..Default::default()
+            },
+            Task {
+                name: "Grandchild".into(),
+                deadline,
+                uid: Some(3),
+                parent: Some(2),
+                ..Default::default()
+            },
+            Task {
+                name: "Unrelated".into(),
+                deadline,
+                uid: Some(4),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn plain_text_task_tracker_assign_missing_uids() {
+        let mut tasks = subtask_tree();
+        tasks[0].uid = None;
+        tasks[3].uid = None;
+        assert!(PlainTextTaskTracker::assign_missing_uids(&mut tasks));
+        assert_eq!(tasks[0].uid, Some(4));
+        assert_eq!(tasks[3].uid, Some(5));
+        assert!(!PlainTextTaskTracker::assign_missing_uids(&mut tasks));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_complete_task_cascade() {
+        let mut tasks = subtask_tree();
+        PlainTextTaskTracker::complete_task_logic(&mut tasks, &[1], true, now());
+        let complete: Vec<bool> = tasks.iter().map(|task| !task.is_open()).collect();
+        assert_eq!(complete, [true, true, true, false]);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_complete_task_no_cascade() {
+        let mut tasks = subtask_tree();
+        PlainTextTaskTracker::complete_task_logic(&mut tasks, &[1], false, now());
+        let complete: Vec<bool> = tasks.iter().map(|task| !task.is_open()).collect();
+        assert_eq!(complete, [false, true, false, false]);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_delete_task_reparents_subtasks() {
+        let mut tasks = subtask_tree();
+        PlainTextTaskTracker::delete_task_logic(&mut tasks, &[0]);
+        let parents: Vec<Option<usize>> = tasks.iter().map(|task| task.parent).collect();
+        assert_eq!(parents, [None, Some(1), None]);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_tree_order() {
+        let tasks = subtask_tree();
+        let order = PlainTextTaskTracker::tree_order(&tasks, &[true; 4]);
+        assert_eq!(order, [(1, 0), (0, 1), (2, 2), (3, 0)]);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_tree_order_hidden_parent() {
+        let tasks = subtask_tree();
+        let order = PlainTextTaskTracker::tree_order(&tasks, &[true, false, true, true]);
+        assert_eq!(order, [(0, 0), (2, 1), (3, 0)]);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_add_dependencies() {
+        let mut tasks = subtask_tree();
+        let res = PlainTextTaskTracker::add_dependencies_logic(&mut tasks, 3, &[0, 2]);
+        assert!(res.is_ok());
+        assert_eq!(tasks[3].depends_on, [2, 3]);
+        assert!(PlainTextTaskTracker::is_blocked(&tasks, &tasks[3]));
+
+        tasks[0].complete(now());
+        tasks[2].complete(now());
+        assert!(!PlainTextTaskTracker::is_blocked(&tasks, &tasks[3]));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_add_dependencies_cycle() {
+        let mut tasks = subtask_tree();
+        tasks[0].depends_on = vec![3];
+        tasks[2].depends_on = vec![4];
+        let res = PlainTextTaskTracker::add_dependencies_logic(&mut tasks, 3, &[0]);
+        assert!(matches!(
+            res,
+            Err(PlainTextTaskTrackerError::DependencyCycle(3))
+        ));
+        assert!(tasks[3].depends_on.is_empty());
+
+        let res = PlainTextTaskTracker::add_dependencies_logic(&mut tasks, 3, &[3]);
+        assert!(matches!(
+            res,
+            Err(PlainTextTaskTrackerError::DependencyCycle(3))
+        ));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_delete_task_removes_dependency() {
+        let mut tasks = subtask_tree();
+        tasks[3].depends_on = vec![3, 1];
+        PlainTextTaskTracker::delete_task_logic(&mut tasks, &[2]);
+        assert_eq!(tasks[2].depends_on, [1]);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_set_status_closes_task() {
+        let mut tasks = subtask_tree();
+        assert!(
+            PlainTextTaskTracker::set_status_logic(
+                &mut tasks,
+                TaskRef::Id(1),
+                Status::Waiting,
+                now()
+            )
+            .is_ok()
+        );
+        assert_eq!(tasks[1].status, Status::Waiting);
+        assert!(
+            PlainTextTaskTracker::set_status_logic(
+                &mut tasks,
+                TaskRef::Id(0),
+                Status::Cancelled,
+                now()
+            )
+            .is_ok()
+        );
+        assert_eq!(tasks[0].status, Status::Cancelled);
+        assert_eq!(
+            PlainTextTaskTracker::incomplete_task_index(&tasks, 0),
+            Some(1)
+        );
+        assert!(matches!(
+            PlainTextTaskTracker::set_status_logic(&mut tasks, TaskRef::Id(3), Status::Done, now()),
+            Err(PlainTextTaskTrackerError::TaskNotFound(3))
+        ));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_set_status_reopens_by_uid() {
+        let mut tasks = subtask_tree();
+        PlainTextTaskTracker::set_status_logic(&mut tasks, TaskRef::Id(1), Status::Done, now())
+            .unwrap();
+        assert_eq!(tasks[1].completed, Some(now()));
+        // Closed tasks have no id left to reopen them with
+        assert!(matches!(
+            PlainTextTaskTracker::set_status_logic(&mut tasks, TaskRef::Id(3), Status::Todo, now()),
+            Err(PlainTextTaskTrackerError::TaskNotFound(3))
+        ));
+        PlainTextTaskTracker::set_status_logic(&mut tasks, TaskRef::Uid(1), Status::Todo, now())
+            .unwrap();
+        assert_eq!(tasks[1].status, Status::Todo);
+        assert_eq!(tasks[1].completed, None);
+        assert!(matches!(
+            PlainTextTaskTracker::set_status_logic(
+                &mut tasks,
+                TaskRef::Uid(9),
+                Status::Todo,
+                now()
+            ),
+            Err(PlainTextTaskTrackerError::UidNotFound(9))
+        ));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_set_status_reopens_archived_task() {
+        let path = std::env::temp_dir().join(format!("on_job_reopen_{}.txt", std::process::id()));
+        let mut tracker = PlainTextTaskTracker::<PipeTable>::new(&path);
+        let mut tasks = subtask_tree();
+        let mut archived = tasks.split_off(3);
+        archived[0].set_status(Status::Cancelled, now());
+        tasks.truncate(1);
+        tracker.write_all(&TaskSet { tasks, archived }).unwrap();
+
+        tracker.set_status(TaskRef::Uid(4), Status::Todo).unwrap();
+        let set = tracker.read_all().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(tracker.archive_path()).unwrap();
+        assert!(set.archived.is_empty());
+        assert_eq!(set.tasks.len(), 2);
+        assert_eq!(set.tasks[1].uid, Some(4));
+        assert_eq!(set.tasks[1].status, Status::Todo);
+        assert_eq!(set.tasks[1].completed, None);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_start_task_stops_other_timer() {
+        let mut tasks = subtask_tree();
+        let now = DateTime::parse_from_rfc3339("2025-03-10T09:00:00+00:00")
+            .unwrap()
+            .to_utc();
+        let later = now + TimeDelta::minutes(45);
+
+        assert!(PlainTextTaskTracker::start_task_logic(&mut tasks, 0, now).is_ok());
+        assert!(PlainTextTaskTracker::start_task_logic(&mut tasks, 3, later).is_ok());
+        assert_eq!(
+            tasks[0].work_log,
+            [WorkInterval {
+                start: now,
+                end: Some(later)
+            }]
+        );
+        assert!(tasks[3].timer_running());
+        assert_eq!(tasks[3].status, Status::InProgress);
+
+        assert!(PlainTextTaskTracker::stop_task_logic(&mut tasks, later).is_ok());
+        assert!(!tasks[3].timer_running());
+        assert!(matches!(
+            PlainTextTaskTracker::stop_task_logic(&mut tasks, later),
+            Err(PlainTextTaskTrackerError::NoTimerRunning)
+        ));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_complete_task_stops_timer() {
+        let mut tasks = subtask_tree();
+        let now = DateTime::parse_from_rfc3339("2025-03-10T09:00:00+00:00")
+            .unwrap()
+            .to_utc();
+        assert!(PlainTextTaskTracker::start_task_logic(&mut tasks, 2, now).is_ok());
+        PlainTextTaskTracker::complete_task_logic(&mut tasks, &[2], false, now);
+        assert!(!tasks[2].timer_running());
+    }
+
+    #[test]
+    fn plain_text_task_tracker_list_hides_waiting_tasks() {
+        let now = DateTime::parse_from_rfc3339("2025-03-10T09:00:00+00:00")
+            .unwrap()
+            .to_utc();
+        let mut tasks = subtask_tree();
+        tasks[0].wait = Some(now + TimeDelta::days(1));
+        tasks[1].wait = Some(now - TimeDelta::days(1));
+        tasks[2].scheduled = Some(now + TimeDelta::hours(2));
+
+        let listed = |filter: &ListFilter| -> Vec<bool> {
+            tasks
+                .iter()
+                .map(|task| PlainTextTaskTracker::is_listed(&tasks, task, filter, now))
+                .collect()
+        };
+        assert_eq!(listed(&ListFilter::default()), [false, true, true, true]);
+        assert_eq!(
+            listed(&ListFilter {
+                deferred: true,
+                ..Default::default()
+            }),
+            [true, true, true, true]
+        );
+        assert_eq!(
+            listed(&ListFilter {
+                scheduled: Some((now, now + TimeDelta::days(1))),
+                ..Default::default()
+            }),
+            [false, false, true, false]
+        );
+    }
+
+    #[test]
+    fn plain_text_task_tracker_snooze_task() {
+        let mut tasks = subtask_tree();
+        let deadline = tasks[3].deadline;
+        PlainTextTaskTracker::snooze_task_logic(&mut tasks, &[3], Snooze::By(TimeDelta::days(2)))
+            .unwrap();
+        assert_eq!(tasks[3].deadline, deadline + TimeDelta::days(2));
+
+        PlainTextTaskTracker::snooze_task_logic(&mut tasks, &[3], Snooze::Until(deadline)).unwrap();
+        assert_eq!(tasks[3].deadline, deadline);
+        assert_eq!(tasks[3].snoozed, 2);
+
+        assert!(matches!(
+            PlainTextTaskTracker::snooze_task_logic(
+                &mut tasks,
+                &[2, 3],
+                Snooze::By(TimeDelta::MAX)
+            ),
+            Err(PlainTextTaskTrackerError::SnoozeTooFar(_))
+        ));
+        assert_eq!(tasks[3].deadline, deadline);
+        assert_eq!(tasks[3].snoozed, 2);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_list_mine_and_waiting() {
+        let now = DateTime::parse_from_rfc3339("2025-03-10T09:00:00+00:00")
+            .unwrap()
+            .to_utc();
+        let mut tasks = subtask_tree();
+        tasks[0].assignee = Some("alice".into());
+        tasks[2].assignee = Some("bob".into());
+        assert!(PlainTextTaskTracker::wait_for_logic(&mut tasks, 3, "carol".into(), now).is_ok());
+        assert!(
+            PlainTextTaskTracker::wait_for_logic(
+                &mut tasks,
+                1,
+                "dave".into(),
+                now - TimeDelta::days(2)
+            )
+            .is_ok()
+        );
+        assert_eq!(tasks[3].status, Status::Waiting);
+        assert_eq!(tasks[3].waiting_for, Some(("carol".into(), now)));
+
+        let listed = |filter: &ListFilter| -> Vec<bool> {
+            tasks
+                .iter()
+                .map(|task| PlainTextTaskTracker::is_listed(&tasks, task, filter, now))
+                .collect()
+        };
+        assert_eq!(
+            listed(&ListFilter {
+                assignee: Some("alice".into()),
+                ..Default::default()
+            }),
+            [true, false, false, false]
+        );
+        let waiting = listed(&ListFilter {
+            waiting: true,
+            ..Default::default()
+        });
+        assert_eq!(waiting, [false, true, false, true]);
+        assert_eq!(
+            PlainTextTaskTracker::longest_waiting_order(&tasks, &waiting),
+            [(1, 0), (3, 0)]
+        );
+
+        assert!(
+            PlainTextTaskTracker::set_status_logic(
+                &mut tasks,
+                TaskRef::Id(3),
+                Status::InProgress,
+                now
+            )
+            .is_ok()
+        );
+        assert_eq!(tasks[3].waiting_for, None);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_list_by_custom_field() {
+        let now = DateTime::parse_from_rfc3339("2025-03-10T09:00:00+00:00")
+            .unwrap()
+            .to_utc();
+        let mut tasks = subtask_tree();
+        tasks[0].fields.insert("severity".into(), "high".into());
+        tasks[1].fields.insert("severity".into(), "low".into());
+
+        let filter = ListFilter {
+            fields: vec![("severity".into(), "high".into())],
+            ..Default::default()
+        };
+        let listed: Vec<bool> = tasks
+            .iter()
+            .map(|task| PlainTextTaskTracker::is_listed(&tasks, task, &filter, now))
+            .collect();
+        assert_eq!(listed, [true, false, false, false]);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_add_links() {
+        let mut tasks = subtask_tree();
+        tasks[3].name = "Review https://example.com/pr/12".into();
+        let res = PlainTextTaskTracker::add_links_logic(
+            &mut tasks,
+            3,
+            vec!["/home/me/spec.pdf".into(), "/home/me/spec.pdf".into()],
+        );
+        assert!(res.is_ok());
+        assert_eq!(
+            tasks[3].references(),
+            ["/home/me/spec.pdf", "https://example.com/pr/12"]
+        );
+        assert!(matches!(
+            PlainTextTaskTracker::add_links_logic(&mut tasks, 4, vec![]),
+            Err(PlainTextTaskTrackerError::TaskNotFound(4))
+        ));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_checklist() {
+        let mut tasks = subtask_tree();
+        for text in ["laptop", "charger", "badge"] {
+            assert!(
+                PlainTextTaskTracker::add_checklist_item_logic(&mut tasks, 3, text.into()).is_ok()
+            );
+        }
+        assert!(PlainTextTaskTracker::check_item_logic(&mut tasks, 3, 2, true).is_ok());
+        assert!(tasks[3].checklist[1].done);
+        assert_eq!(
+            PlainTextTaskTracker::display_name(&tasks, &tasks[3], 0),
+            "Unrelated [1/3]"
+        );
+        assert!(matches!(
+            PlainTextTaskTracker::check_item_logic(&mut tasks, 3, 0, true),
+            Err(PlainTextTaskTrackerError::ChecklistItemNotFound(3, 0))
+        ));
+        assert!(matches!(
+            PlainTextTaskTracker::check_item_logic(&mut tasks, 3, 4, true),
+            Err(PlainTextTaskTrackerError::ChecklistItemNotFound(3, 4))
+        ));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_show_details() {
+        let mut tasks = subtask_tree();
+        tasks[0].depends_on = vec![4];
+        tasks[0].notes = vec!["first".into(), "second".into()];
+        let deadline = tasks[0].deadline;
+
+        let details = PlainTextTaskTracker::task_details(&tasks, 0, deadline + TimeDelta::hours(2));
+        assert_eq!(details.due_in.as_deref(), Some("-2h"));
+        assert!(details.overdue && details.blocked);
+        assert_eq!(details.parent_name, Some("Parent"));
+        assert_eq!(details.depends_on_names, ["Unrelated"]);
+
+        let rows = PlainTextTaskTracker::detail_rows(&details, &Utc);
+        let row = |key: &str| {
+            rows.iter()
+                .find(|(row_key, _)| row_key == key)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(row("Due"), Some("2025-03-17 22:00 +00:00"));
+        assert_eq!(row("Overdue by"), Some("2h"));
+        assert_eq!(row("Depends on"), Some("Unrelated (blocked)"));
+        assert_eq!(row("Notes"), Some("first"));
+        assert_eq!(row(""), Some("second"));
+        assert_eq!(row("Tags"), None);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_show_closed_task() {
+        let mut tasks = subtask_tree();
+        tasks[0].complete(now());
+
+        let idx = PlainTextTaskTracker::task_index(&tasks, TaskRef::Uid(2)).unwrap();
+        let details = PlainTextTaskTracker::task_details(&tasks, idx, now());
+        assert_eq!(details.id, None);
+        assert_eq!(details.due_in, None);
+        let rows = PlainTextTaskTracker::detail_rows(&details, &Utc);
+        let row = |key: &str| {
+            rows.iter()
+                .find(|(row_key, _)| row_key == key)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(row("Id"), None);
+        assert_eq!(row("Uid"), Some("2"));
+        assert_eq!(row("Status"), Some("done"));
+        assert_eq!(row("Completed"), Some("2025-03-10 09:00 +00:00"));
+
+        // Open tasks after it keep the ids `list` shows them with
+        let details = PlainTextTaskTracker::task_details(&tasks, 3, now());
+        assert_eq!(details.id, Some(2));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_select_ranges_and_conditions() {
+        let mut tasks = subtask_tree();
+        tasks[0].tags = Some(vec!["sprint12".into()]);
+        tasks[2].tags = Some(vec!["sprint12".into(), "backend".into()]);
+        tasks[1].complete(now());
+
+        // Ids count open tasks only, so id 1 is the grandchild at index 2
+        let selection = Selection {
+            ids: vec![IdRange(1..=2), IdRange(0..=0)],
+            ..Default::default()
+        };
+        assert_eq!(
+            PlainTextTaskTracker::select_logic(&tasks, &selection, now()).unwrap(),
+            [0, 2, 3]
+        );
+        assert!(!selection.is_single());
+
+        let selection = Selection {
+            conditions: vec![Condition::Tag("sprint12".into())],
+            ..Default::default()
+        };
+        assert_eq!(
+            PlainTextTaskTracker::select_logic(&tasks, &selection, now()).unwrap(),
+            [0, 2]
+        );
+
+        let selection = Selection {
+            ids: vec![IdRange(2..=2)],
+            conditions: vec![Condition::Tag("sprint12".into())],
+        };
+        assert!(matches!(
+            PlainTextTaskTracker::select_logic(&tasks, &selection, now()),
+            Err(PlainTextTaskTrackerError::NothingSelected)
+        ));
+    }
+
+    #[test]
+    fn plain_text_task_tracker_bulk_delete_and_edit() {
+        let mut tasks = subtask_tree();
+        PlainTextTaskTracker::edit_task_logic(
+            &mut tasks,
+            &[0, 3],
+            &TaskEdit {
+                add_tags: vec!["sprint12".into()],
+                priority: Some(Priority::High),
+                ..Default::default()
+            },
+        );
+        assert_eq!(tasks[3].tags, Some(vec!["sprint12".into()]));
+        assert_eq!(tasks[0].priority, Some(Priority::High));
+        PlainTextTaskTracker::edit_task_logic(
+            &mut tasks,
+            &[3],
+            &TaskEdit {
+                remove_tags: vec!["sprint12".into()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(tasks[3].tags, None);
+
+        PlainTextTaskTracker::delete_task_logic(&mut tasks, &[0, 1]);
+        let names: Vec<&str> = tasks.iter().map(|task| task.name.as_str()).collect();
+        assert_eq!(names, ["Grandchild", "Unrelated"]);
+        assert_eq!(tasks[0].parent, None);
+    }
+
+    #[test]
+    fn plain_text_task_tracker_archive_closed_tasks() {
+        let mut tasks = subtask_tree();
+        tasks[1].set_status(Status::Done, now() - TimeDelta::days(40));
+        tasks[0].set_status(Status::Done, now() - TimeDelta::days(10));
+        tasks[3].status = Status::Cancelled;
+        assert_eq!(PlainTextTaskTracker::archive_logic(&tasks, now()), [3]);
+
+        tasks[2].set_status(Status::Done, now() - TimeDelta::days(1));
+        assert_eq!(
+            PlainTextTaskTracker::archive_logic(&tasks, now() - TimeDelta::days(30)),
+            [1, 3]
+        );
+    }
+
+    #[test]
+    fn plain_text_task_tracker_archive_path() {
+        assert_eq!(
+            PlainTextTaskTracker::<PipeTable>::new("work/tasks.txt").archive_path(),
+            PathBuf::from("work/tasks.archive.txt")
+        );
+        assert_eq!(
+            PlainTextTaskTracker::<PipeTable>::new("tasks").archive_path(),
+            PathBuf::from("tasks.archive")
+        );
+    }
+
+    #[test]
+    fn plain_text_task_tracker_import_skips_known_uuids() {
+        let mut tasks = subtask_tree();
+        tasks[3].fields.insert("uuid".into(), "a5b9e3e4".into());
+        let archive = vec![Task {
+            name: "Archived".into(),
+            uid: Some(5),
+            fields: [("uuid".into(), "0f4b7c1d".into())].into(),
+            ..Default::default()
+        }];
+        // Another task file numbers its tasks from 1 as well
+        let imported = vec![
+            Task {
+                name: "Foreign parent".into(),
+                uid: Some(1),
+                ..Default::default()
+            },
+            Task {
+                name: "Foreign child".into(),
+                uid: Some(2),
+                parent: Some(1),
+                depends_on: vec![3, 9],
+                ..Default::default()
+            },
+            Task {
+                name: "Unrelated again".into(),
+                uid: Some(3),
+                fields: [("uuid".into(), "a5b9e3e4".into())].into(),
+                ..Default::default()
+            },
+            Task {
+                name: "Archived again".into(),
+                uid: Some(4),
+                fields: [("uuid".into(), "0f4b7c1d".into())].into(),
+                ..Default::default()
+            },
+            Task {
+                name: "New".into(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            PlainTextTaskTracker::import_logic(&mut tasks, &archive, imported),
+            3
+        );
+        let added: Vec<(&str, Option<usize>)> = tasks[4..]
+            .iter()
+            .map(|task| (task.name.as_str(), task.uid))
+            .collect();
+        assert_eq!(
+            added,
+            [
+                ("Foreign parent", Some(6)),
+                ("Foreign child", Some(7)),
+                ("New", Some(8))
+            ]
+        );
+        // References follow the imported tasks to their new uids, or to the task imported before
+        assert_eq!(tasks[5].parent, Some(6));
+        assert_eq!(tasks[5].depends_on, [4]);
+        assert_eq!(tasks[4].parent, None);
+    }
 }
@@ -0,0 +1,401 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use crate::task_tracker::task::{Estimate, Status, Task};
+
+/// What tracked time is summed up by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportGrouping {
+    Task,
+    Tag,
+    Day,
+}
+
+impl Display for ReportGrouping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ReportGrouping::Task => "task".fmt(f),
+            ReportGrouping::Tag => "tag".fmt(f),
+            ReportGrouping::Day => "day".fmt(f),
+        }
+    }
+}
+
+impl FromStr for ReportGrouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "task" => Ok(ReportGrouping::Task),
+            "tag" => Ok(ReportGrouping::Tag),
+            "day" => Ok(ReportGrouping::Day),
+            _ => Err(format!("\"{s}\" is not one of task, tag or day")),
+        }
+    }
+}
+
+pub(super) const UNTAGGED: &str = "(untagged)";
+
+/// Splits `from..until` at each midnight in `tz`, so time can be attributed to the day it was spent on
+fn split_by_day<Tz: TimeZone>(
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+    tz: &Tz,
+) -> Vec<(NaiveDate, TimeDelta)> {
+    let mut days = Vec::new();
+    let mut cursor = from;
+    while cursor < until {
+        let day = cursor.with_timezone(tz).date_naive();
+        let next_midnight = day
+            .checked_add_days(Days::new(1))
+            .and_then(|next_day| next_day.and_hms_opt(0, 0, 0))
+            .and_then(|midnight| tz.from_local_datetime(&midnight).earliest())
+            .map(|midnight| midnight.to_utc())
+            .unwrap_or(until)
+            .min(until);
+        days.push((day, next_midnight - cursor));
+        cursor = next_midnight;
+    }
+    days
+}
+
+/// Time tracked since `since` (or ever) until `now`, summed up per task, tag or day
+pub(super) fn time_by<Tz: TimeZone>(
+    tasks: &[Task],
+    since: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    grouping: ReportGrouping,
+    tz: &Tz,
+) -> Vec<(String, TimeDelta)> {
+    let since = since.unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let mut totals: HashMap<String, TimeDelta> = HashMap::new();
+
+    for task in tasks {
+        for interval in &task.work_log {
+            let from = interval.start.max(since);
+            let until = interval.end.unwrap_or(now).min(now);
+            if from >= until {
+                continue;
+            }
+            match grouping {
+                ReportGrouping::Task => {
+                    let key = match task.status {
+                        Status::Cancelled => format!("{} (cancelled)", task.name),
+                        _ => task.name.clone(),
+                    };
+                    *totals.entry(key).or_default() += until - from;
+                }
+                ReportGrouping::Tag => match task.tags.as_deref() {
+                    Some(tags) if !tags.is_empty() => {
+                        for tag in tags {
+                            *totals.entry(tag.clone()).or_default() += until - from;
+                        }
+                    }
+                    _ => *totals.entry(UNTAGGED.into()).or_default() += until - from,
+                },
+                ReportGrouping::Day => {
+                    for (day, worked) in split_by_day(from, until, tz) {
+                        *totals
+                            .entry(day.format("%Y-%m-%d").to_string())
+                            .or_default() += worked;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut totals: Vec<(String, TimeDelta)> = totals.into_iter().collect();
+    match grouping {
+        ReportGrouping::Day => totals.sort(),
+        ReportGrouping::Task | ReportGrouping::Tag => {
+            totals.sort_by(|(key_a, worked_a), (key_b, worked_b)| {
+                worked_b.cmp(worked_a).then(key_a.cmp(key_b))
+            })
+        }
+    }
+    totals
+}
+
+/// Estimated against tracked effort for the tasks with one tag
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct EstimateSummary {
+    pub tag: String,
+    /// Sum of the estimates given as a length of time
+    pub estimated: TimeDelta,
+    /// Time tracked on the tasks estimated in time
+    pub actual: TimeDelta,
+    /// Sum of the estimates given in points
+    pub points: u32,
+    /// Time tracked on the tasks estimated in points
+    pub point_actual: TimeDelta,
+    /// Time tracked on cancelled tasks, which are left out of the comparison
+    pub cancelled: TimeDelta,
+}
+
+/// Compares estimates with tracked time per tag, for tasks due within `since..until`.
+/// Cancelled tasks only count towards the time spent on cancelled work.
+pub(super) fn estimates_by_tag(
+    tasks: &[Task],
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Vec<EstimateSummary> {
+    let mut summaries: HashMap<String, EstimateSummary> = HashMap::new();
+    let in_range = |task: &&Task| {
+        since.is_none_or(|since| task.deadline >= since)
+            && until.is_none_or(|until| task.deadline < until)
+    };
+
+    for task in tasks.iter().filter(in_range) {
+        let cancelled = task.status == Status::Cancelled;
+        let estimate = match task.estimate {
+            Some(estimate) if !cancelled => Some(estimate),
+            _ if cancelled && task.tracked(now) > TimeDelta::zero() => None,
+            _ => continue,
+        };
+        let tags = match task.tags.as_deref() {
+            Some(tags) if !tags.is_empty() => tags.to_vec(),
+            _ => vec![UNTAGGED.to_string()],
+        };
+        for tag in tags {
+            let summary = summaries
+                .entry(tag.clone())
+                .or_insert_with(|| EstimateSummary {
+                    tag,
+                    ..Default::default()
+                });
+            match estimate {
+                Some(Estimate::Time(time)) => {
+                    summary.estimated += time;
+                    summary.actual += task.tracked(now);
+                }
+                Some(Estimate::Points(points)) => {
+                    summary.points += points;
+                    summary.point_actual += task.tracked(now);
+                }
+                None => summary.cancelled += task.tracked(now),
+            }
+        }
+    }
+
+    let mut summaries: Vec<EstimateSummary> = summaries.into_values().collect();
+    summaries.sort_by(|summary_a, summary_b| summary_a.tag.cmp(&summary_b.tag));
+    summaries
+}
+
+/// The end of the week `now` is in, i.e. the coming Monday at midnight in `tz`
+pub(super) fn week_end<Tz: TimeZone>(now: DateTime<Utc>, tz: &Tz) -> DateTime<Utc> {
+    let today = now.with_timezone(tz).date_naive();
+    let days_left = 7 - u64::from(today.weekday().num_days_from_monday());
+    today
+        .checked_add_days(Days::new(days_left))
+        .and_then(|monday| {
+            tz.from_local_datetime(&monday.and_time(NaiveTime::MIN))
+                .earliest()
+        })
+        .map(|monday| monday.to_utc())
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+pub(super) fn format_hours(delta: TimeDelta) -> String {
+    format!("{:.2}", delta.num_seconds() as f64 / 3600.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::task::WorkInterval;
+
+    fn time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    fn tracked_tasks() -> Vec<Task> {
+        vec![
+            Task {
+                name: "Invoice".into(),
+                tags: Some(vec!["client".into(), "admin".into()]),
+                work_log: vec![
+                    WorkInterval {
+                        start: time("2025-03-09T23:00:00+00:00"),
+                        end: Some(time("2025-03-10T01:00:00+00:00")),
+                    },
+                    WorkInterval {
+                        start: time("2025-03-11T09:00:00+00:00"),
+                        end: None,
+                    },
+                ],
+                ..Default::default()
+            },
+            Task {
+                name: "Email".into(),
+                work_log: vec![WorkInterval {
+                    start: time("2025-03-10T12:00:00+00:00"),
+                    end: Some(time("2025-03-10T12:30:00+00:00")),
+                }],
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn time_by_task() {
+        let totals = time_by(
+            &tracked_tasks(),
+            None,
+            time("2025-03-11T10:00:00+00:00"),
+            ReportGrouping::Task,
+            &Utc,
+        );
+        assert_eq!(
+            totals,
+            [
+                ("Invoice".to_string(), TimeDelta::hours(3)),
+                ("Email".to_string(), TimeDelta::minutes(30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn time_by_tag_since() {
+        let totals = time_by(
+            &tracked_tasks(),
+            Some(time("2025-03-10T00:00:00+00:00")),
+            time("2025-03-11T10:00:00+00:00"),
+            ReportGrouping::Tag,
+            &Utc,
+        );
+        assert_eq!(
+            totals,
+            [
+                ("admin".to_string(), TimeDelta::hours(2)),
+                ("client".to_string(), TimeDelta::hours(2)),
+                (UNTAGGED.to_string(), TimeDelta::minutes(30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn estimates_compared_per_tag() {
+        let mut tasks = tracked_tasks();
+        tasks[0].estimate = Some(Estimate::Time(TimeDelta::hours(2)));
+        tasks[0].deadline = time("2025-03-12T17:00:00+00:00");
+        tasks[1].estimate = Some(Estimate::Points(2));
+        tasks[1].deadline = time("2025-03-12T17:00:00+00:00");
+        tasks.push(Task {
+            name: "Later".into(),
+            estimate: Some(Estimate::Points(8)),
+            deadline: time("2025-04-01T17:00:00+00:00"),
+            ..Default::default()
+        });
+
+        let summaries = estimates_by_tag(
+            &tasks,
+            None,
+            Some(time("2025-03-15T00:00:00+00:00")),
+            time("2025-03-11T10:00:00+00:00"),
+        );
+        assert_eq!(
+            summaries,
+            [
+                EstimateSummary {
+                    tag: UNTAGGED.into(),
+                    points: 2,
+                    point_actual: TimeDelta::minutes(30),
+                    ..Default::default()
+                },
+                EstimateSummary {
+                    tag: "admin".into(),
+                    estimated: TimeDelta::hours(2),
+                    actual: TimeDelta::hours(3),
+                    ..Default::default()
+                },
+                EstimateSummary {
+                    tag: "client".into(),
+                    estimated: TimeDelta::hours(2),
+                    actual: TimeDelta::hours(3),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn estimates_leave_out_cancelled_tasks() {
+        let mut tasks = tracked_tasks();
+        tasks[0].estimate = Some(Estimate::Time(TimeDelta::hours(2)));
+        tasks[0].status = Status::Cancelled;
+        tasks[1].estimate = Some(Estimate::Time(TimeDelta::hours(1)));
+
+        let summaries = estimates_by_tag(&tasks, None, None, time("2025-03-11T10:00:00+00:00"));
+        assert_eq!(
+            summaries,
+            [
+                EstimateSummary {
+                    tag: UNTAGGED.into(),
+                    estimated: TimeDelta::hours(1),
+                    actual: TimeDelta::minutes(30),
+                    ..Default::default()
+                },
+                EstimateSummary {
+                    tag: "admin".into(),
+                    cancelled: TimeDelta::hours(3),
+                    ..Default::default()
+                },
+                EstimateSummary {
+                    tag: "client".into(),
+                    cancelled: TimeDelta::hours(3),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn time_by_task_marks_cancelled() {
+        let mut tasks = tracked_tasks();
+        tasks[1].status = Status::Cancelled;
+        let totals = time_by(
+            &tasks,
+            None,
+            time("2025-03-11T10:00:00+00:00"),
+            ReportGrouping::Task,
+            &Utc,
+        );
+        assert_eq!(
+            totals[1],
+            ("Email (cancelled)".to_string(), TimeDelta::minutes(30))
+        );
+    }
+
+    #[test]
+    fn week_end_is_next_monday() {
+        assert_eq!(
+            week_end(time("2025-03-12T10:00:00+00:00"), &Utc),
+            time("2025-03-17T00:00:00+00:00")
+        );
+        assert_eq!(
+            week_end(time("2025-03-10T00:00:00+00:00"), &Utc),
+            time("2025-03-17T00:00:00+00:00")
+        );
+    }
+
+    #[test]
+    fn time_by_day_splits_at_midnight() {
+        let totals = time_by(
+            &tracked_tasks(),
+            None,
+            time("2025-03-11T10:00:00+00:00"),
+            ReportGrouping::Day,
+            &Utc,
+        );
+        assert_eq!(
+            totals,
+            [
+                ("2025-03-09".to_string(), TimeDelta::hours(1)),
+                ("2025-03-10".to_string(), TimeDelta::minutes(90)),
+                ("2025-03-11".to_string(), TimeDelta::hours(1)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use std::{ops::RangeInclusive, str::FromStr};
+
+use crate::task_tracker::task::{Priority, Status, Task};
+
+/// An id as shown by `list`, or an inclusive range of them like `3-7`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdRange(pub RangeInclusive<usize>);
+
+impl FromStr for IdRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |id: &str| {
+            id.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("\"{s}\" is neither an id nor a range of ids like 3-7"))
+        };
+        match s.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                match first <= last {
+                    true => Ok(IdRange(first..=last)),
+                    false => Err(format!("\"{s}\" ends before it starts")),
+                }
+            }
+            None => parse(s).map(|id| IdRange(id..=id)),
+        }
+    }
+}
+
+/// One condition of a `--where` filter, written as `key:value`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Tag(String),
+    Status(Status),
+    Priority(Priority),
+    Assignee(String),
+    /// A custom field, written as `field.<name>:<value>`
+    Field(String, String),
+    Overdue,
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "overdue" {
+            return Ok(Condition::Overdue);
+        }
+        let (key, value) = s
+            .split_once(':')
+            .map(|(key, value)| (key.trim(), value.trim().to_string()))
+            .ok_or_else(|| format!("\"{s}\" is not of the form `key:value` or `overdue`"))?;
+        match key {
+            "tag" => Ok(Condition::Tag(value)),
+            "status" => value
+                .parse()
+                .map(Condition::Status)
+                .map_err(|e| e.to_string()),
+            "priority" => value
+                .parse()
+                .map(Condition::Priority)
+                .map_err(|e| e.to_string()),
+            "assignee" => Ok(Condition::Assignee(value)),
+            _ => match key.strip_prefix("field.") {
+                Some(name) if !name.is_empty() => Ok(Condition::Field(name.into(), value)),
+                _ => Err(format!(
+                    "\"{key}\" is not one of tag, status, priority, assignee or field.<name>"
+                )),
+            },
+        }
+    }
+}
+
+impl Condition {
+    pub(super) fn matches(&self, task: &Task, now: DateTime<Utc>) -> bool {
+        match self {
+            Condition::Tag(tag) => task.tags.as_deref().unwrap_or_default().contains(tag),
+            Condition::Status(status) => task.status == *status,
+            Condition::Priority(priority) => task.priority == Some(*priority),
+            Condition::Assignee(assignee) => task.assignee.as_ref() == Some(assignee),
+            Condition::Field(name, value) => task.fields.get(name) == Some(value),
+            Condition::Overdue => task.deadline < now,
+        }
+    }
+}
+
+/// Which tasks a bulk command applies to: the given ids, or every open task when there are none,
+/// narrowed down to the tasks meeting every condition
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub ids: Vec<IdRange>,
+    pub conditions: Vec<Condition>,
+}
+
+impl Selection {
+    /// Whether this is exactly one id, which needs no confirmation before changing it
+    pub fn is_single(&self) -> bool {
+        self.conditions.is_empty()
+            && matches!(self.ids.as_slice(), [IdRange(range)] if range.start() == range.end())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_id_ranges() {
+        assert_eq!(IdRange::from_str("4"), Ok(IdRange(4..=4)));
+        assert_eq!(IdRange::from_str("3-7"), Ok(IdRange(3..=7)));
+        assert!(IdRange::from_str("7-3").is_err());
+        assert!(IdRange::from_str("a-3").is_err());
+    }
+
+    #[test]
+    fn parse_conditions() {
+        assert_eq!(
+            Condition::from_str("tag:sprint12"),
+            Ok(Condition::Tag("sprint12".into()))
+        );
+        assert_eq!(
+            Condition::from_str("status: in-progress"),
+            Ok(Condition::Status(Status::InProgress))
+        );
+        assert_eq!(
+            Condition::from_str("field.ticket:OPS-1"),
+            Ok(Condition::Field("ticket".into(), "OPS-1".into()))
+        );
+        assert_eq!(Condition::from_str("overdue"), Ok(Condition::Overdue));
+        assert!(Condition::from_str("colour:red").is_err());
+        assert!(Condition::from_str("sprint12").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// One of the two files a tracker keeps its tasks in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskFile {
+    Tasks,
+    Archive,
+}
+
+/// Where the task file and its archive are read and written, so another process can own them
+pub trait Storage {
+    fn exists(&self, file: TaskFile) -> Result<bool, std::io::Error>;
+    /// The whole file, failing when it doesn't exist
+    fn read(&self, file: TaskFile) -> Result<Vec<u8>, std::io::Error>;
+    /// Replaces the whole file, creating it if needed
+    fn write(&self, file: TaskFile, contents: &[u8]) -> Result<(), std::io::Error>;
+    /// Adds to the end of the file, creating it if needed
+    fn append(&self, file: TaskFile, contents: &[u8]) -> Result<(), std::io::Error>;
+    /// Deletes the file, if it exists
+    fn remove(&self, file: TaskFile) -> Result<(), std::io::Error>;
+    /// Makes the changes of one command final, for storage holding them back until the command is
+    /// done. Fails with a [`Conflict`] when another process changed what the command read
+    fn commit(&self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+    /// Drops the changes held back since the last commit
+    fn rollback(&self) {}
+}
+
+/// A file changed since it was read, so the changes made from what was read were refused
+#[derive(Debug)]
+pub struct Conflict(pub TaskFile);
+
+impl Conflict {
+    /// Whether `e` is a conflict, after which the command can be run again on what is there now
+    pub fn is(e: &std::io::Error) -> bool {
+        e.get_ref().is_some_and(|inner| inner.is::<Conflict>())
+    }
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            TaskFile::Tasks => "the task file changed since it was read".fmt(f),
+            TaskFile::Archive => "the archive changed since it was read".fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// The files themselves, read and written directly
+pub struct Files {
+    pub tasks: PathBuf,
+    pub archive: PathBuf,
+}
+
+impl Files {
+    fn path(&self, file: TaskFile) -> &PathBuf {
+        match file {
+            TaskFile::Tasks => &self.tasks,
+            TaskFile::Archive => &self.archive,
+        }
+    }
+}
+
+impl Storage for Files {
+    fn exists(&self, file: TaskFile) -> Result<bool, std::io::Error> {
+        self.path(file).try_exists()
+    }
+
+    fn read(&self, file: TaskFile) -> Result<Vec<u8>, std::io::Error> {
+        std::fs::read(self.path(file))
+    }
+
+    fn write(&self, file: TaskFile, contents: &[u8]) -> Result<(), std::io::Error> {
+        std::fs::write(self.path(file), contents)
+    }
+
+    fn append(&self, file: TaskFile, contents: &[u8]) -> Result<(), std::io::Error> {
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(self.path(file))?
+            .write_all(contents)
+    }
+
+    fn remove(&self, file: TaskFile) -> Result<(), std::io::Error> {
+        match std::fs::remove_file(self.path(file)) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+/// Files kept in memory only, to try out what a format writes without touching the disk
+#[derive(Default)]
+pub struct Memory {
+    tasks: RefCell<Option<Vec<u8>>>,
+    archive: RefCell<Option<Vec<u8>>>,
+}
+
+impl Memory {
+    fn file(&self, file: TaskFile) -> &RefCell<Option<Vec<u8>>> {
+        match file {
+            TaskFile::Tasks => &self.tasks,
+            TaskFile::Archive => &self.archive,
+        }
+    }
+}
+
+impl Storage for Memory {
+    fn exists(&self, file: TaskFile) -> Result<bool, std::io::Error> {
+        Ok(self.file(file).borrow().is_some())
+    }
+
+    fn read(&self, file: TaskFile) -> Result<Vec<u8>, std::io::Error> {
+        self.file(file)
+            .borrow()
+            .clone()
+            .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+    }
+
+    fn write(&self, file: TaskFile, contents: &[u8]) -> Result<(), std::io::Error> {
+        *self.file(file).borrow_mut() = Some(contents.to_vec());
+        Ok(())
+    }
+
+    fn append(&self, file: TaskFile, contents: &[u8]) -> Result<(), std::io::Error> {
+        self.file(file)
+            .borrow_mut()
+            .get_or_insert_default()
+            .extend_from_slice(contents);
+        Ok(())
+    }
+
+    fn remove(&self, file: TaskFile) -> Result<(), std::io::Error> {
+        *self.file(file).borrow_mut() = None;
+        Ok(())
+    }
+}
@@ -1,16 +1,287 @@
-use chrono::{DateTime, Local, ParseError, Utc};
-use std::{
-    error::Error,
-    fmt::Display,
-    str::{FromStr, ParseBoolError},
-};
-
-#[derive(Debug, Default, PartialEq, Eq)]
-pub(super) struct Task {
+use crate::utils::{find_urls, format_duration, parse_duration};
+use chrono::{DateTime, Local, NaiveTime, ParseError, SecondsFormat, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error, fmt::Display, num::ParseIntError, str::FromStr};
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
     pub name: String,
     pub tags: Option<Vec<String>>,
     pub deadline: DateTime<Utc>,
-    pub complete: bool,
+    #[serde(default)]
+    pub status: Status,
+    /// Stable identifier used to link tasks together, unlike the positional id shown by `list`
+    pub uid: Option<usize>,
+    /// `uid` of the task this one is a subtask of
+    pub parent: Option<usize>,
+    /// `uid`s of the tasks that must be complete before this one can start
+    #[serde(default)]
+    pub depends_on: Vec<usize>,
+    pub priority: Option<Priority>,
+    /// When the task was added, unknown for tasks added before this was tracked
+    pub created: Option<DateTime<Utc>>,
+    /// When the task was done or cancelled
+    pub completed: Option<DateTime<Utc>>,
+    /// Periods spent working on the task, the last of which may still be running
+    #[serde(default)]
+    pub work_log: Vec<WorkInterval>,
+    pub estimate: Option<Estimate>,
+    /// When work on the task is planned to begin
+    pub scheduled: Option<DateTime<Utc>>,
+    /// The task is hidden from `list` until this moment
+    pub wait: Option<DateTime<Utc>>,
+    /// How many times the deadline was pushed back with `snooze`
+    #[serde(default)]
+    pub snoozed: u32,
+    /// Who the task belongs to on a shared task file
+    pub assignee: Option<String>,
+    /// Who a waiting task is waiting on, and since when
+    pub waiting_for: Option<(String, DateTime<Utc>)>,
+    /// Custom fields declared in the config, by name
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    /// URLs and file paths the task refers to
+    #[serde(default)]
+    pub links: Vec<String>,
+    /// Small steps that don't deserve a subtask of their own
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+/// Expected effort, either as a length of time or in story points
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Estimate {
+    Time(TimeDelta),
+    Points(u32),
+}
+
+impl From<Estimate> for String {
+    fn from(value: Estimate) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Estimate {
+    type Error = ParseTaskError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Estimate::Time(time) => format_duration(time).fmt(f),
+            Estimate::Points(points) => write!(f, "{points}pts"),
+        }
+    }
+}
+
+impl FromStr for Estimate {
+    type Err = ParseTaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim().to_lowercase();
+        let points = ["pts", "pt", "points", "point"]
+            .iter()
+            .find_map(|suffix| trimmed.strip_suffix(suffix));
+        match points {
+            Some(points) => points.trim().parse().map(Estimate::Points).ok(),
+            None => parse_duration(&trimmed).map(Estimate::Time),
+        }
+        .ok_or_else(|| ParseTaskError::InvalidEstimate(s.into()))
+    }
+}
+
+/// One line of a task's checklist, written as `[x] text` or `[ ] text`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub text: String,
+    pub done: bool,
+}
+
+impl Display for ChecklistItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mark = if self.done { 'x' } else { ' ' };
+        write!(f, "[{mark}] {}", self.text)
+    }
+}
+
+impl FromStr for ChecklistItem {
+    type Err = ParseTaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (done, text) = match s.trim() {
+            item if item.starts_with("[x]") => (true, &item[3..]),
+            item if item.starts_with("[ ]") => (false, &item[3..]),
+            _ => return Err(ParseTaskError::InvalidTaskFormat),
+        };
+        Ok(ChecklistItem {
+            text: text.trim().into(),
+            done,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkInterval {
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl Display for WorkInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}",
+            self.start.to_rfc3339_opts(SecondsFormat::Secs, false),
+            self.end
+                .map(|end| end.to_rfc3339_opts(SecondsFormat::Secs, false))
+                .unwrap_or_default()
+        )
+    }
+}
+
+impl FromStr for WorkInterval {
+    type Err = ParseTaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('/').ok_or(ParseTaskError::InvalidTaskFormat)?;
+        Ok(WorkInterval {
+            start: DateTime::parse_from_rfc3339(start.trim())?.to_utc(),
+            end: match end.trim() {
+                "" => None,
+                end => Some(DateTime::parse_from_rfc3339(end)?.to_utc()),
+            },
+        })
+    }
+}
+
+/// Where a task is in its workflow, from `Todo` until it is either `Done` or `Cancelled`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Waiting,
+    Cancelled,
+    Done,
+}
+
+impl Status {
+    /// Open tasks still need work, and are the ones given an id by `list`
+    pub fn is_open(&self) -> bool {
+        matches!(self, Status::Todo | Status::InProgress | Status::Waiting)
+    }
+}
+
+impl From<Status> for String {
+    fn from(value: Status) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Status {
+    type Error = ParseTaskError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Status::Todo => "todo".fmt(f),
+            Status::InProgress => "in-progress".fmt(f),
+            Status::Waiting => "waiting".fmt(f),
+            Status::Cancelled => "cancelled".fmt(f),
+            Status::Done => "done".fmt(f),
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = ParseTaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            // Files written before statuses existed only knew complete or not
+            "false" | "todo" => Ok(Status::Todo),
+            "in-progress" | "in_progress" | "inprogress" | "started" => Ok(Status::InProgress),
+            "waiting" => Ok(Status::Waiting),
+            "cancelled" | "canceled" => Ok(Status::Cancelled),
+            "true" | "done" => Ok(Status::Done),
+            _ => Err(ParseTaskError::InvalidStatus(s.into())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl From<Priority> for String {
+    fn from(value: Priority) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Priority {
+    type Error = ParseTaskError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Priority::Low => "low".fmt(f),
+            Priority::Medium => "medium".fmt(f),
+            Priority::High => "high".fmt(f),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = ParseTaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "l" | "low" => Ok(Priority::Low),
+            "m" | "medium" => Ok(Priority::Medium),
+            "h" | "high" => Ok(Priority::High),
+            _ => Err(ParseTaskError::InvalidPriority(s.into())),
+        }
+    }
+}
+
+/// Fails when `text` can't be kept in a task file, where `|` separates a task's cells and a line
+/// break ends the task
+pub fn check_text(text: &str) -> Result<(), String> {
+    match text.contains(['|', '\n', '\r']) {
+        true => Err(format!(
+            "\"{}\" cannot contain '|' or line breaks",
+            text.escape_debug()
+        )),
+        false => Ok(()),
+    }
+}
+
+/// The time of day deadlines given only as a day fall at, the end of the working day
+pub fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(17, 0, 0).expect("Hardcoded values")
 }
 
 impl Task {
@@ -19,12 +290,97 @@ impl Task {
             name,
             tags,
             deadline,
-            complete: false,
+            status: Status::Todo,
+            ..Default::default()
+        }
+    }
+
+    pub fn complete(&mut self, now: DateTime<Utc>) {
+        self.set_status(Status::Done, now);
+    }
+
+    /// Changes the status, stopping the timer and recording when once the task is closed
+    pub fn set_status(&mut self, status: Status, now: DateTime<Utc>) {
+        if status.is_open() {
+            self.completed = None;
+        } else if self.is_open() {
+            self.completed = Some(now);
+            self.stop_timer(now);
+        }
+        self.status = status;
+        if status != Status::Waiting {
+            self.waiting_for = None;
+        }
+    }
+
+    /// The task's links, followed by any URL written in its name
+    pub fn references(&self) -> Vec<String> {
+        let mut references = self.links.clone();
+        for url in find_urls(&self.name) {
+            if !references.iter().any(|reference| reference == url) {
+                references.push(url.into());
+            }
+        }
+        references
+    }
+
+    /// Marks the task as waiting on `who` from `now` on
+    pub fn wait_for(&mut self, who: String, now: DateTime<Utc>) {
+        self.set_status(Status::Waiting, now);
+        self.waiting_for = Some((who, now));
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.status.is_open()
+    }
+
+    /// Time logged on the task, counting a running timer up to `now`
+    pub fn tracked(&self, now: DateTime<Utc>) -> TimeDelta {
+        self.work_log
+            .iter()
+            .map(|interval| interval.end.unwrap_or(now) - interval.start)
+            .sum()
+    }
+
+    /// Estimated time not yet covered by tracked time, if the estimate is a length of time
+    pub fn remaining_estimate(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        match self.estimate {
+            Some(Estimate::Time(estimate)) => {
+                Some((estimate - self.tracked(now)).max(TimeDelta::zero()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_waiting(&self, now: DateTime<Utc>) -> bool {
+        self.wait.is_some_and(|wait| wait > now)
+    }
+
+    pub fn timer_running(&self) -> bool {
+        self.work_log.iter().any(|interval| interval.end.is_none())
+    }
+
+    pub fn start_timer(&mut self, now: DateTime<Utc>) {
+        if !self.timer_running() {
+            self.work_log.push(WorkInterval {
+                start: now,
+                end: None,
+            });
         }
     }
 
-    pub fn complete(&mut self) {
-        self.complete = true;
+    /// Ends the running interval, returning whether there was one
+    pub fn stop_timer(&mut self, now: DateTime<Utc>) -> bool {
+        let mut stopped = false;
+        for interval in self
+            .work_log
+            .iter_mut()
+            .filter(|interval| interval.end.is_none())
+        {
+            interval.end = Some(now.max(interval.start));
+            stopped = true;
+        }
+        stopped
     }
 
     pub fn local_deadline(&self) -> String {
@@ -37,30 +393,155 @@ impl Task {
     pub fn export_deadline(&self) -> String {
         self.deadline.to_rfc3339()
     }
+
+    /// Optional `key=value` columns written after the four fixed columns
+    pub(super) fn attributes(&self) -> Vec<(&'static str, String)> {
+        let mut attributes = Vec::new();
+        if let Some(uid) = self.uid {
+            attributes.push(("uid", uid.to_string()));
+        }
+        if let Some(parent) = self.parent {
+            attributes.push(("parent", parent.to_string()));
+        }
+        if !self.depends_on.is_empty() {
+            let depends_on: Vec<String> =
+                self.depends_on.iter().map(|uid| uid.to_string()).collect();
+            attributes.push(("depends", depends_on.join(",")));
+        }
+        if let Some(priority) = self.priority {
+            attributes.push(("priority", priority.to_string()));
+        }
+        if let Some(created) = self.created {
+            attributes.push((
+                "created",
+                created.to_rfc3339_opts(SecondsFormat::Secs, false),
+            ));
+        }
+        if let Some(completed) = self.completed {
+            attributes.push((
+                "completed",
+                completed.to_rfc3339_opts(SecondsFormat::Secs, false),
+            ));
+        }
+        for interval in &self.work_log {
+            attributes.push(("worked", interval.to_string()));
+        }
+        if let Some(estimate) = self.estimate {
+            attributes.push(("estimate", estimate.to_string()));
+        }
+        if let Some(scheduled) = self.scheduled {
+            attributes.push(("scheduled", scheduled.to_rfc3339()));
+        }
+        if let Some(wait) = self.wait {
+            attributes.push(("wait", wait.to_rfc3339()));
+        }
+        if self.snoozed > 0 {
+            attributes.push(("snoozed", self.snoozed.to_string()));
+        }
+        for link in &self.links {
+            attributes.push(("link", link.clone()));
+        }
+        for item in &self.checklist {
+            attributes.push(("check", item.to_string()));
+        }
+        for note in &self.notes {
+            attributes.push(("note", note.clone()));
+        }
+        if let Some(assignee) = &self.assignee {
+            attributes.push(("assignee", assignee.clone()));
+        }
+        if let Some((who, since)) = &self.waiting_for {
+            attributes.push((
+                "waiting_for",
+                format!(
+                    "{who}/{}",
+                    since.to_rfc3339_opts(SecondsFormat::Secs, false)
+                ),
+            ));
+        }
+        attributes
+    }
+
+    pub(super) fn set_attribute(&mut self, key: &str, value: &str) -> Result<(), ParseTaskError> {
+        match key {
+            "uid" => self.uid = Some(value.parse()?),
+            "parent" => self.parent = Some(value.parse()?),
+            "depends" => {
+                self.depends_on = value
+                    .split(',')
+                    .map(|uid| uid.trim().parse())
+                    .collect::<Result<_, _>>()?
+            }
+            "priority" => self.priority = Some(value.parse()?),
+            "created" => self.created = Some(DateTime::parse_from_rfc3339(value)?.to_utc()),
+            "completed" => self.completed = Some(DateTime::parse_from_rfc3339(value)?.to_utc()),
+            "worked" => self.work_log.push(value.parse()?),
+            "estimate" => self.estimate = Some(value.parse()?),
+            "scheduled" => self.scheduled = Some(DateTime::parse_from_rfc3339(value)?.to_utc()),
+            "wait" => self.wait = Some(DateTime::parse_from_rfc3339(value)?.to_utc()),
+            "snoozed" => self.snoozed = value.parse()?,
+            "link" => self.links.push(value.into()),
+            "check" => self.checklist.push(value.parse()?),
+            "note" => self.notes.push(value.into()),
+            "assignee" => self.assignee = Some(value.into()),
+            "waiting_for" => {
+                let (who, since) = value
+                    .rsplit_once('/')
+                    .ok_or(ParseTaskError::InvalidTaskFormat)?;
+                self.waiting_for = Some((
+                    who.trim().into(),
+                    DateTime::parse_from_rfc3339(since.trim())?.to_utc(),
+                ));
+            }
+            _ => match key.strip_prefix("field.") {
+                Some(name) if !name.is_empty() => {
+                    self.fields.insert(name.into(), value.into());
+                }
+                _ => return Err(ParseTaskError::InvalidTaskFormat),
+            },
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ParseTaskError {
-    ParseBool(ParseBoolError),
+    InvalidStatus(String),
+    ParseInt(ParseIntError),
     InvalidTaskFormat,
     InvalidDateFormat(ParseError),
+    InvalidPriority(String),
+    InvalidEstimate(String),
+    /// A JSON, YAML or TOML task file that doesn't hold tasks, with the parser's reason
+    InvalidDocument(String),
 }
 
 impl Display for ParseTaskError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
-            ParseTaskError::ParseBool(ref e) => e.fmt(f),
+            ParseTaskError::InvalidStatus(ref status) => write!(
+                f,
+                "\"{status}\" is not one of todo, in-progress, waiting, cancelled or done"
+            ),
+            ParseTaskError::ParseInt(ref e) => e.fmt(f),
             ParseTaskError::InvalidDateFormat(ref e) => e.fmt(f),
             ParseTaskError::InvalidTaskFormat => {
                 "provided string could not be converted to a task".fmt(f)
             }
+            ParseTaskError::InvalidPriority(ref priority) => {
+                write!(f, "\"{priority}\" is not one of low, medium or high")
+            }
+            ParseTaskError::InvalidEstimate(ref estimate) => {
+                write!(f, "\"{estimate}\" is neither a length of time nor points")
+            }
+            ParseTaskError::InvalidDocument(ref reason) => reason.fmt(f),
         }
     }
 }
 
-impl From<std::str::ParseBoolError> for ParseTaskError {
-    fn from(value: std::str::ParseBoolError) -> Self {
-        ParseTaskError::ParseBool(value)
+impl From<ParseIntError> for ParseTaskError {
+    fn from(value: ParseIntError) -> Self {
+        ParseTaskError::ParseInt(value)
     }
 }
 
@@ -83,7 +564,7 @@ impl FromStr for Task {
             .map(|x| x.trim())
             .collect();
 
-        if let [name, tags_str, complete_str, deadline] = vals[..] {
+        if let [name, tags_str, status, deadline, ref attributes @ ..] = vals[..] {
             let tags = if tags_str
                 .split(",")
                 .filter(|tag| tag.trim() != "")
@@ -100,13 +581,21 @@ impl FromStr for Task {
                 )
             };
             let deadline = DateTime::parse_from_rfc3339(deadline.trim())?.to_utc();
-            let complete = complete_str.parse::<bool>()?;
-            Ok(Task {
+            let status = status.parse::<Status>()?;
+            let mut task = Task {
                 name: name.into(),
                 tags,
                 deadline,
-                complete,
-            })
+                status,
+                ..Default::default()
+            };
+            for attribute in attributes {
+                let (key, value) = attribute
+                    .split_once('=')
+                    .ok_or(ParseTaskError::InvalidTaskFormat)?;
+                task.set_attribute(key.trim(), value.trim())?;
+            }
+            Ok(task)
         } else {
             Err(ParseTaskError::InvalidTaskFormat)
         }
@@ -123,7 +612,7 @@ impl From<Task> for String {
                 .as_ref()
                 .map(|x| x.join(", "))
                 .unwrap_or("".into()),
-            value.complete,
+            value.status,
             value.local_deadline()
         )
     }
@@ -139,9 +628,16 @@ impl Display for Task {
                 .as_ref()
                 .map(|x| x.join(", "))
                 .unwrap_or("".into()),
-            self.complete,
+            self.status,
             self.export_deadline()
-        )
+        )?;
+        for (key, value) in self.attributes() {
+            write!(f, " {key}={value} |")?;
+        }
+        for (name, value) in &self.fields {
+            write!(f, " field.{name}={value} |")?;
+        }
+        Ok(())
     }
 }
 
@@ -158,7 +654,8 @@ mod test {
             deadline: DateTime::parse_from_rfc3339("2025-03-17T22:00:00+00:00")
                 .unwrap()
                 .to_utc(),
-            complete: false,
+            status: Status::Todo,
+            ..Default::default()
         });
         let parsed_task = Task::from_str(task_str);
         assert_eq!(expected_task, parsed_task);
@@ -173,7 +670,8 @@ mod test {
             deadline: DateTime::parse_from_rfc3339("2025-03-17T22:00:00+00:00")
                 .unwrap()
                 .to_utc(),
-            complete: false,
+            status: Status::Todo,
+            ..Default::default()
         });
         let parsed_task_res = Task::from_str(task_str);
         assert_eq!(expected_task, parsed_task_res);
@@ -188,10 +686,129 @@ mod test {
             deadline: DateTime::parse_from_rfc3339("2025-03-17T22:00:00+00:00")
                 .unwrap()
                 .to_utc(),
-            complete: false,
+            status: Status::Todo,
+            ..Default::default()
+        };
+        let parsed_task = Task::from_str(task_str).unwrap();
+        assert_eq!(expected_task, parsed_task);
+    }
+
+    #[test]
+    fn parse_good_task_with_attributes() {
+        let task_str = "| Task 1 |  | waiting | 2025-03-17T22:00:00+00:00 | uid=4 | parent=2 | depends=1,3 | priority=high | created=2025-03-01T09:30:00+00:00 | completed=2025-03-04T11:00:00+00:00 | worked=2025-03-02T09:00:00+00:00/2025-03-02T10:15:00+00:00 | worked=2025-03-03T08:00:00+00:00/ | estimate=1h30m | scheduled=2025-03-15T09:00:00+00:00 | wait=2025-03-14T00:00:00+00:00 | snoozed=2 | link=https://example.com/issues/7 | link=/home/me/spec.pdf | check=[x] charger | check=[ ] badge | note=Ask about the budget first | assignee=alice | waiting_for=bob/2025-03-12T15:00:00+00:00 | field.customer=acme | field.ticket=OPS-12 |";
+        let expected_task = Task {
+            name: "Task 1".into(),
+            tags: None,
+            deadline: DateTime::parse_from_rfc3339("2025-03-17T22:00:00+00:00")
+                .unwrap()
+                .to_utc(),
+            status: Status::Waiting,
+            uid: Some(4),
+            parent: Some(2),
+            depends_on: vec![1, 3],
+            priority: Some(Priority::High),
+            created: Some(
+                DateTime::parse_from_rfc3339("2025-03-01T09:30:00+00:00")
+                    .unwrap()
+                    .to_utc(),
+            ),
+            completed: Some(
+                DateTime::parse_from_rfc3339("2025-03-04T11:00:00+00:00")
+                    .unwrap()
+                    .to_utc(),
+            ),
+            work_log: vec![
+                WorkInterval {
+                    start: DateTime::parse_from_rfc3339("2025-03-02T09:00:00+00:00")
+                        .unwrap()
+                        .to_utc(),
+                    end: Some(
+                        DateTime::parse_from_rfc3339("2025-03-02T10:15:00+00:00")
+                            .unwrap()
+                            .to_utc(),
+                    ),
+                },
+                WorkInterval {
+                    start: DateTime::parse_from_rfc3339("2025-03-03T08:00:00+00:00")
+                        .unwrap()
+                        .to_utc(),
+                    end: None,
+                },
+            ],
+            estimate: Some(Estimate::Time(TimeDelta::minutes(90))),
+            scheduled: Some(
+                DateTime::parse_from_rfc3339("2025-03-15T09:00:00+00:00")
+                    .unwrap()
+                    .to_utc(),
+            ),
+            wait: Some(
+                DateTime::parse_from_rfc3339("2025-03-14T00:00:00+00:00")
+                    .unwrap()
+                    .to_utc(),
+            ),
+            snoozed: 2,
+            assignee: Some("alice".into()),
+            waiting_for: Some((
+                "bob".into(),
+                DateTime::parse_from_rfc3339("2025-03-12T15:00:00+00:00")
+                    .unwrap()
+                    .to_utc(),
+            )),
+            fields: BTreeMap::from([
+                ("customer".into(), "acme".into()),
+                ("ticket".into(), "OPS-12".into()),
+            ]),
+            links: vec![
+                "https://example.com/issues/7".into(),
+                "/home/me/spec.pdf".into(),
+            ],
+            checklist: vec![
+                ChecklistItem {
+                    text: "charger".into(),
+                    done: true,
+                },
+                ChecklistItem {
+                    text: "badge".into(),
+                    done: false,
+                },
+            ],
+            notes: vec!["Ask about the budget first".into()],
         };
         let parsed_task = Task::from_str(task_str).unwrap();
         assert_eq!(expected_task, parsed_task);
+        assert_eq!(task_str, parsed_task.to_string());
+    }
+
+    #[test]
+    fn parse_bad_task_bad_attribute() {
+        let task_str = "| Task 1 | | false | 2025-03-17T22:00:00+00:00 | parent=first |";
+        let parsed_task_res = Task::from_str(task_str);
+        assert!(matches!(parsed_task_res, Err(ParseTaskError::ParseInt(_))));
+    }
+
+    #[test]
+    fn parse_bad_task_bad_priority() {
+        let task_str = "| Task 1 | | false | 2025-03-17T22:00:00+00:00 | priority=urgent |";
+        let parsed_task_res = Task::from_str(task_str);
+        assert_eq!(
+            parsed_task_res,
+            Err(ParseTaskError::InvalidPriority("urgent".into()))
+        );
+    }
+
+    #[test]
+    fn parse_estimates() {
+        assert_eq!(
+            Estimate::from_str("1h30m"),
+            Ok(Estimate::Time(TimeDelta::minutes(90)))
+        );
+        assert_eq!(Estimate::from_str("3pts"), Ok(Estimate::Points(3)));
+        assert_eq!(Estimate::from_str("3 points"), Ok(Estimate::Points(3)));
+        assert_eq!(
+            Estimate::from_str("soon"),
+            Err(ParseTaskError::InvalidEstimate("soon".into()))
+        );
+        assert_eq!(Estimate::Time(TimeDelta::minutes(90)).to_string(), "1h30m");
     }
 
     #[test]
@@ -236,10 +853,28 @@ mod test {
     }
 
     #[test]
-    fn parse_bad_task_bad_boolean() {
+    fn parse_bad_task_bad_status() {
         let task_str = "| Task | project, ugh | maybe | 2025-03-20T22:00:00+00:00 |";
         let parsed_task_res = Task::from_str(task_str);
-        assert!(matches!(parsed_task_res, Err(ParseTaskError::ParseBool(_))));
+        assert!(matches!(
+            parsed_task_res,
+            Err(ParseTaskError::InvalidStatus(_))
+        ));
+    }
+
+    #[test]
+    fn parse_statuses() {
+        assert_eq!(Status::from_str("false"), Ok(Status::Todo));
+        assert_eq!(Status::from_str("true"), Ok(Status::Done));
+        for status in [
+            Status::Todo,
+            Status::InProgress,
+            Status::Waiting,
+            Status::Cancelled,
+            Status::Done,
+        ] {
+            assert_eq!(Status::from_str(&status.to_string()), Ok(status));
+        }
     }
 
     #[test]
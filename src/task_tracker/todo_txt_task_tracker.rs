@@ -0,0 +1,416 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+
+use crate::task_tracker::plain_text_task_tracker::{PlainTextTaskTracker, TaskFileFormat};
+use crate::task_tracker::task::{ParseTaskError, Priority, Status, Task, end_of_day};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Keeps tasks in a todo.txt file, so they can also be edited by the many todo.txt apps
+pub type TodoTxtTaskTracker = PlainTextTaskTracker<TodoTxt>;
+
+/// The todo.txt format: `x 2025-03-09 2025-03-01 Call the plumber +home @phone due:2025-03-12`.
+///
+/// Tags are written as `+projects`, except tags starting with `@` which stay contexts.
+/// Whatever todo.txt has no syntax for is kept in `key:value` pairs, so words of the name that
+/// look like one have their `:` written as `%3A`.
+pub struct TodoTxt;
+
+/// Task attributes written as `key:value` pairs under the task file's own keys
+const ATTRIBUTE_KEYS: [&str; 13] = [
+    "uid",
+    "parent",
+    "depends",
+    "worked",
+    "estimate",
+    "scheduled",
+    "wait",
+    "snoozed",
+    "link",
+    "check",
+    "note",
+    "assignee",
+    "waiting_for",
+];
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+impl TaskFileFormat for TodoTxt {
+    fn read_tasks<B: BufRead>(reader: B) -> Result<Vec<Task>, ParseTaskError> {
+        let now = Utc::now();
+        let mut tasks = reader
+            .lines()
+            .map_while(Result::ok)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| TodoTxt::parse_line(&line, &Local, now))
+            .collect::<Result<Vec<_>, _>>()?;
+        tasks.sort_by_key(|task| task.deadline);
+        Ok(tasks)
+    }
+
+    fn append_task<W: Write>(writer: &mut W, task: &Task) -> Result<(), std::io::Error> {
+        writeln!(writer, "{}", TodoTxt::format_line(task, &Local))
+    }
+
+    /// todo.txt apps move done tasks from `todo.txt` to `done.txt`
+    fn archive_path(file_path: &Path) -> PathBuf {
+        if file_path.file_name().is_some_and(|name| name == "todo.txt") {
+            return file_path.with_file_name("done.txt");
+        }
+        let mut name = file_path.file_stem().unwrap_or_default().to_owned();
+        name.push(".done.txt");
+        file_path.with_file_name(name)
+    }
+}
+
+impl TodoTxt {
+    fn format_line<Tz: TimeZone>(task: &Task, tz: &Tz) -> String {
+        let date = |date_time: &DateTime<Utc>| {
+            date_time
+                .with_timezone(tz)
+                .date_naive()
+                .format(DATE_FORMAT)
+                .to_string()
+        };
+        let priority = task.priority.map(|priority| match priority {
+            Priority::High => 'A',
+            Priority::Medium => 'B',
+            Priority::Low => 'C',
+        });
+
+        let mut words = Vec::new();
+        if task.status.is_open() {
+            if let Some(priority) = priority {
+                words.push(format!("({priority})"));
+            }
+            words.extend(task.created.as_ref().map(date));
+        } else {
+            words.push("x".into());
+            // todo.txt only allows a creation date after a completion date
+            if let Some(completed) = &task.completed {
+                words.push(date(completed));
+                words.extend(task.created.as_ref().map(date));
+            }
+        }
+        words.push(Self::encode_name(&task.name));
+        for tag in task.tags.iter().flatten() {
+            let tag = tag.split_whitespace().collect::<Vec<_>>().join("_");
+            words.push(if tag.starts_with('@') {
+                tag
+            } else {
+                format!("+{tag}")
+            });
+        }
+
+        let deadline = task.deadline.with_timezone(tz).naive_local();
+        words.push(format!("due:{}", deadline.format(DATE_FORMAT)));
+        if deadline.time().second() != 0 {
+            words.push(format!("due_time:{}", deadline.format("%H:%M:%S")));
+        } else if deadline.time() != end_of_day() {
+            words.push(format!("due_time:{}", deadline.format("%H:%M")));
+        }
+        if !matches!(task.status, Status::Todo | Status::Done) {
+            words.push(format!("status:{}", task.status));
+        }
+        if !task.status.is_open()
+            && let Some(priority) = priority
+        {
+            words.push(format!("pri:{priority}"));
+        }
+        for (key, value) in task.attributes() {
+            if ATTRIBUTE_KEYS.contains(&key) {
+                words.push(format!("{key}:{}", Self::encode(&value)));
+            }
+        }
+        for (name, value) in &task.fields {
+            words.push(format!("{name}:{}", Self::encode(value)));
+        }
+        words.join(" ")
+    }
+
+    fn parse_line<Tz: TimeZone>(
+        line: &str,
+        tz: &Tz,
+        now: DateTime<Utc>,
+    ) -> Result<Task, ParseTaskError> {
+        let at = |date: NaiveDate, time: NaiveTime| {
+            tz.from_local_datetime(&date.and_time(time))
+                .earliest()
+                .map(|date_time| date_time.to_utc())
+                .ok_or(ParseTaskError::InvalidTaskFormat)
+        };
+        let date = |word: &&str| NaiveDate::parse_from_str(word, DATE_FORMAT).ok();
+        let time = |word: &str| {
+            NaiveTime::parse_from_str(word, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(word, "%H:%M"))
+                .ok()
+        };
+
+        let mut task = Task::default();
+        let mut words = line.split_whitespace().peekable();
+        if words.next_if_eq(&"x").is_some() {
+            task.status = Status::Done;
+            if let Some(completed) = words.peek().and_then(date) {
+                words.next();
+                task.completed = Some(at(completed, NaiveTime::MIN)?);
+                if let Some(created) = words.peek().and_then(date) {
+                    words.next();
+                    task.created = Some(at(created, NaiveTime::MIN)?);
+                }
+            }
+        } else {
+            if let Some(priority) = words.peek().and_then(|word| Self::priority(word)) {
+                words.next();
+                task.priority = Some(priority);
+            }
+            if let Some(created) = words.peek().and_then(date) {
+                words.next();
+                task.created = Some(at(created, NaiveTime::MIN)?);
+            }
+        }
+
+        let mut name = Vec::new();
+        let mut tags = Vec::new();
+        let mut due = None;
+        let mut due_time = None;
+        for word in words {
+            if let Some(project) = word.strip_prefix('+').filter(|project| !project.is_empty()) {
+                tags.push(project.into());
+                continue;
+            }
+            if word.len() > 1 && word.starts_with('@') {
+                tags.push(word.into());
+                continue;
+            }
+            match Self::key_value(word) {
+                // Other tools may write due dates this format doesn't read, like `due:tomorrow`,
+                // which are kept as custom fields rather than failing the whole file
+                Some(("due", value)) if date(&value).is_some() => due = date(&value),
+                Some(("due_time", value)) if time(value).is_some() => due_time = time(value),
+                Some(("status", value)) => task.status = value.parse()?,
+                Some(("pri", value)) => {
+                    task.priority = Some(
+                        Self::priority(&format!("({value})"))
+                            .ok_or(ParseTaskError::InvalidPriority(value.into()))?,
+                    )
+                }
+                Some((key, value)) if ATTRIBUTE_KEYS.contains(&key) => {
+                    task.set_attribute(key, &Self::decode(value))?
+                }
+                Some((key, value)) => {
+                    task.fields.insert(key.into(), Self::decode(value));
+                }
+                None => name.push(Self::decode_name(word)),
+            }
+        }
+        if name.is_empty() {
+            return Err(ParseTaskError::InvalidTaskFormat);
+        }
+        task.name = name.join(" ");
+        task.tags = (!tags.is_empty()).then_some(tags);
+        let day = due.unwrap_or_else(|| now.with_timezone(tz).date_naive());
+        task.deadline = at(day, due_time.unwrap_or_else(end_of_day))?;
+        Ok(task)
+    }
+
+    /// `(A)` is the most urgent priority todo.txt has, and `(C)` the least urgent one kept
+    fn priority(word: &str) -> Option<Priority> {
+        match word {
+            "(A)" => Some(Priority::High),
+            "(B)" => Some(Priority::Medium),
+            "(C)" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+
+    /// A `key:value` pair, but not a URL or a time like `10:30` inside the task name
+    fn key_value(word: &str) -> Option<(&str, &str)> {
+        let (key, value) = word.split_once(':')?;
+        let valid_key = key.starts_with(|c: char| c.is_ascii_alphabetic())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        (valid_key && !value.is_empty() && !value.starts_with("//")).then_some((key, value))
+    }
+
+    /// The name, with the words that would be read as `key:value` pairs encoded
+    fn encode_name(name: &str) -> String {
+        name.split(' ')
+            .map(|word| {
+                // A `%` is only encoded where it could be mistaken for an encoded character
+                let word = match word.contains("%3A") || word.contains("%25") {
+                    true => word.replace('%', "%25"),
+                    false => word.into(),
+                };
+                match Self::key_value(&word) {
+                    Some(_) => word.replace(':', "%3A"),
+                    None => word,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn decode_name(word: &str) -> String {
+        word.replace("%3A", ":").replace("%25", "%")
+    }
+
+    /// Values can't hold spaces, which split todo.txt words
+    fn encode(value: &str) -> String {
+        value.replace('%', "%25").replace(' ', "%20")
+    }
+
+    fn decode(value: &str) -> String {
+        value.replace("%20", " ").replace("%25", "%")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_tracker::exchange::test_util::{date_time as at, tz};
+    use crate::task_tracker::task::ChecklistItem;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn parses_todo_txt_lines() {
+        let now = at("2025-03-05T10:00:00+01:00");
+        let task = TodoTxt::parse_line(
+            "(A) 2025-03-01 Call the plumber +home @phone due:2025-03-12 t:2025-03-10",
+            &tz(),
+            now,
+        )
+        .unwrap();
+        assert_eq!(task.name, "Call the plumber");
+        assert_eq!(task.priority, Some(Priority::High));
+        assert_eq!(task.created, Some(at("2025-03-01T00:00:00+01:00")));
+        assert_eq!(task.tags, Some(vec!["home".into(), "@phone".into()]));
+        assert_eq!(task.deadline, at("2025-03-12T17:00:00+01:00"));
+        assert_eq!(task.fields.get("t").map(String::as_str), Some("2025-03-10"));
+        assert_eq!(task.status, Status::Todo);
+
+        let done =
+            TodoTxt::parse_line("x 2025-03-09 2025-03-01 Meet at 10:30", &tz(), now).unwrap();
+        assert_eq!(done.status, Status::Done);
+        assert_eq!(done.name, "Meet at 10:30");
+        assert_eq!(done.completed, Some(at("2025-03-09T00:00:00+01:00")));
+        assert_eq!(done.created, Some(at("2025-03-01T00:00:00+01:00")));
+        // Without a due date the task is due at the end of today
+        assert_eq!(done.deadline, at("2025-03-05T17:00:00+01:00"));
+
+        let with_url = TodoTxt::parse_line("Read https://example.com", &tz(), now).unwrap();
+        assert_eq!(with_url.name, "Read https://example.com");
+        assert!(with_url.fields.is_empty());
+
+        assert!(TodoTxt::parse_line("+home due:2025-03-12", &tz(), now).is_err());
+
+        // A due date written by another tool that can't be read is kept, and the task stays
+        let tomorrow = TodoTxt::parse_line("Pay rent due:tomorrow", &tz(), now).unwrap();
+        assert_eq!(tomorrow.name, "Pay rent");
+        assert_eq!(tomorrow.deadline, at("2025-03-05T17:00:00+01:00"));
+        assert_eq!(
+            tomorrow.fields.get("due").map(String::as_str),
+            Some("tomorrow")
+        );
+    }
+
+    #[test]
+    fn writes_todo_txt_lines() {
+        let task = Task {
+            name: "Call the plumber".into(),
+            tags: Some(vec!["home".into(), "@phone".into(), "big job".into()]),
+            deadline: at("2025-03-12T17:00:00+01:00"),
+            priority: Some(Priority::Medium),
+            created: Some(at("2025-03-01T09:30:00+01:00")),
+            ..Default::default()
+        };
+        assert_eq!(
+            TodoTxt::format_line(&task, &tz()),
+            "(B) 2025-03-01 Call the plumber +home @phone +big_job due:2025-03-12"
+        );
+
+        let cancelled = Task {
+            name: "Fix the roof".into(),
+            deadline: at("2025-03-12T09:15:00+01:00"),
+            status: Status::Cancelled,
+            priority: Some(Priority::Low),
+            completed: Some(at("2025-03-10T12:00:00+01:00")),
+            ..Default::default()
+        };
+        assert_eq!(
+            TodoTxt::format_line(&cancelled, &tz()),
+            "x 2025-03-10 Fix the roof due:2025-03-12 due_time:09:15 status:cancelled pri:C"
+        );
+    }
+
+    #[test]
+    fn round_trips_every_attribute() {
+        let now = at("2025-03-05T10:00:00+01:00");
+        let mut task = Task {
+            name: "Write the report".into(),
+            tags: Some(vec!["work".into()]),
+            deadline: at("2025-03-12T09:15:30+01:00"),
+            status: Status::InProgress,
+            uid: Some(4),
+            parent: Some(1),
+            depends_on: vec![2, 3],
+            estimate: Some("2h".parse().unwrap()),
+            scheduled: Some(at("2025-03-10T09:00:00+01:00")),
+            assignee: Some("Sam".into()),
+            waiting_for: Some(("Alex Doe".into(), at("2025-03-04T08:00:00Z"))),
+            links: vec!["https://example.com/a b".into()],
+            checklist: vec![ChecklistItem {
+                text: "Draft 100%".into(),
+                done: true,
+            }],
+            notes: vec!["Ask about the  numbers".into()],
+            snoozed: 1,
+            ..Default::default()
+        };
+        task.fields.insert("client".into(), "Acme Corp".into());
+        let line = TodoTxt::format_line(&task, &tz());
+        assert!(!line.contains("Acme Corp"), "{line}");
+        assert_eq!(TodoTxt::parse_line(&line, &tz(), now).unwrap(), task);
+
+        // Only the day of creation and completion is kept
+        let done = Task {
+            name: "Ship it".into(),
+            deadline: at("2025-03-12T17:00:00+01:00"),
+            status: Status::Done,
+            created: Some(at("2025-03-01T00:00:00+01:00")),
+            completed: Some(at("2025-03-11T00:00:00+01:00") + TimeDelta::hours(15)),
+            ..Default::default()
+        };
+        let parsed = TodoTxt::parse_line(&TodoTxt::format_line(&done, &tz()), &tz(), now).unwrap();
+        assert_eq!(parsed.completed, Some(at("2025-03-11T00:00:00+01:00")));
+        assert_eq!(parsed.created, done.created);
+    }
+
+    #[test]
+    fn round_trips_names_with_colons() {
+        let now = at("2025-03-05T10:00:00+01:00");
+        let task = Task {
+            name: "Email Bob re:budget, 100% due:Friday at 10:30 via https://a.b/c%3A".into(),
+            deadline: at("2025-03-12T17:00:00+01:00"),
+            ..Default::default()
+        };
+        let line = TodoTxt::format_line(&task, &tz());
+        assert_eq!(
+            line,
+            "Email Bob re%3Abudget, 100% due%3AFriday at 10:30 via https://a.b/c%253A \
+             due:2025-03-12"
+        );
+        assert_eq!(TodoTxt::parse_line(&line, &tz(), now).unwrap(), task);
+    }
+
+    #[test]
+    fn archives_to_done_txt() {
+        assert_eq!(
+            TodoTxt::archive_path(Path::new("/home/me/todo.txt")),
+            Path::new("/home/me/done.txt")
+        );
+        assert_eq!(
+            TodoTxt::archive_path(Path::new("work.txt")),
+            Path::new("work.done.txt")
+        );
+    }
+}
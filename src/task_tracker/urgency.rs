@@ -0,0 +1,173 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::HashMap;
+
+use crate::config::{Config, ConfigError};
+use crate::task_tracker::task::{Priority, Task};
+
+/// How much each factor contributes to a task's urgency, configurable under `next.`
+#[derive(Debug, PartialEq)]
+pub struct UrgencyWeights {
+    pub due: f64,
+    pub overdue: f64,
+    pub age: f64,
+    pub blocked: f64,
+    pub high: f64,
+    pub medium: f64,
+    pub low: f64,
+    pub tags: HashMap<String, f64>,
+}
+
+impl Default for UrgencyWeights {
+    fn default() -> Self {
+        UrgencyWeights {
+            due: 12.0,
+            overdue: 6.0,
+            age: 2.0,
+            blocked: -5.0,
+            high: 6.0,
+            medium: 3.9,
+            low: 1.8,
+            tags: HashMap::new(),
+        }
+    }
+}
+
+impl TryFrom<&Config> for UrgencyWeights {
+    type Error = ConfigError;
+
+    fn try_from(config: &Config) -> Result<Self, Self::Error> {
+        let default = UrgencyWeights::default();
+        let tags = config
+            .section("next.tag")
+            .map(|(tag, _)| {
+                Ok((
+                    tag.to_string(),
+                    config.get_or(&format!("next.tag.{tag}"), 0.0)?,
+                ))
+            })
+            .collect::<Result<_, ConfigError>>()?;
+        Ok(UrgencyWeights {
+            due: config.get_or("next.weight.due", default.due)?,
+            overdue: config.get_or("next.weight.overdue", default.overdue)?,
+            age: config.get_or("next.weight.age", default.age)?,
+            blocked: config.get_or("next.weight.blocked", default.blocked)?,
+            high: config.get_or("next.priority.high", default.high)?,
+            medium: config.get_or("next.priority.medium", default.medium)?,
+            low: config.get_or("next.priority.low", default.low)?,
+            tags,
+        })
+    }
+}
+
+/// Deadlines further away than this don't add any urgency
+const DUE_HORIZON_DAYS: f64 = 14.0;
+/// Tasks this old get the full age weight
+const AGE_HORIZON_DAYS: f64 = 365.0;
+
+fn days(delta: TimeDelta) -> f64 {
+    delta.num_seconds() as f64 / 86_400.0
+}
+
+/// Each non-zero contribution to the urgency of `task`, so the total can be explained
+pub(super) fn urgency(
+    task: &Task,
+    blocked: bool,
+    now: DateTime<Utc>,
+    weights: &UrgencyWeights,
+) -> Vec<(String, f64)> {
+    let mut factors = Vec::new();
+
+    let days_left = days(task.deadline - now);
+    let proximity = ((DUE_HORIZON_DAYS - days_left) / DUE_HORIZON_DAYS).clamp(0.0, 1.0);
+    factors.push(("due".to_string(), weights.due * proximity));
+    if days_left < 0.0 {
+        factors.push(("overdue".to_string(), weights.overdue));
+    }
+
+    let priority = match task.priority {
+        Some(Priority::High) => weights.high,
+        Some(Priority::Medium) => weights.medium,
+        Some(Priority::Low) => weights.low,
+        None => 0.0,
+    };
+    factors.push(("priority".to_string(), priority));
+
+    for tag in task.tags.as_deref().unwrap_or_default() {
+        if let Some(weight) = weights.tags.get(tag) {
+            factors.push((format!("tag {tag}"), *weight));
+        }
+    }
+
+    if let Some(created) = task.created {
+        let age = (days(now - created) / AGE_HORIZON_DAYS).clamp(0.0, 1.0);
+        factors.push(("age".to_string(), weights.age * age));
+    }
+    if blocked {
+        factors.push(("blocked".to_string(), weights.blocked));
+    }
+
+    factors.retain(|(_, score)| *score != 0.0);
+    factors
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-03-10T12:00:00+00:00")
+            .unwrap()
+            .to_utc()
+    }
+
+    #[test]
+    fn urgency_far_deadline_only_counts_priority() {
+        let task = Task {
+            deadline: now() + TimeDelta::days(30),
+            priority: Some(Priority::Medium),
+            ..Default::default()
+        };
+        let factors = urgency(&task, false, now(), &UrgencyWeights::default());
+        assert_eq!(factors, [("priority".to_string(), 3.9)]);
+    }
+
+    #[test]
+    fn urgency_overdue_blocked_and_tagged() {
+        let task = Task {
+            deadline: now() - TimeDelta::days(1),
+            tags: Some(vec!["urgent".into(), "other".into()]),
+            created: Some(now() - TimeDelta::days(365)),
+            ..Default::default()
+        };
+        let weights = UrgencyWeights {
+            tags: HashMap::from([("urgent".to_string(), 5.0)]),
+            ..Default::default()
+        };
+        let factors = urgency(&task, true, now(), &weights);
+        assert_eq!(
+            factors,
+            [
+                ("due".to_string(), 12.0),
+                ("overdue".to_string(), 6.0),
+                ("tag urgent".to_string(), 5.0),
+                ("age".to_string(), 2.0),
+                ("blocked".to_string(), -5.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn urgency_weights_from_config() {
+        let config = Config::from_str("next.weight.due = 20\nnext.tag.urgent = 4.5").unwrap();
+        let weights = UrgencyWeights::try_from(&config).unwrap();
+        assert_eq!(
+            weights,
+            UrgencyWeights {
+                due: 20.0,
+                tags: HashMap::from([("urgent".to_string(), 4.5)]),
+                ..Default::default()
+            }
+        );
+    }
+}
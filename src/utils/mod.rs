@@ -1,15 +1,24 @@
+use chrono::TimeDelta;
+use std::io::{IsTerminal, Write};
+use std::process::Command;
+
+#[derive(Clone, Copy)]
 pub enum TextEffect {
     StrikeThrough,
+    Dim,
     Red,
     Green,
+    Yellow,
 }
 
 impl TextEffect {
     pub fn ansi_code(&self) -> &str {
         match *self {
             TextEffect::StrikeThrough => "9",
+            TextEffect::Dim => "2",
             TextEffect::Red => "31",
             TextEffect::Green => "32",
+            TextEffect::Yellow => "33",
         }
     }
 }
@@ -23,3 +32,191 @@ impl std::fmt::Display for TextEffect {
 pub fn add_text_effect(text: &str, effect_str: TextEffect) -> String {
     format!("\x1b[{}m{}\x1b[0m", effect_str, text)
 }
+
+pub struct TableRow {
+    pub cells: Vec<String>,
+    pub effects: Vec<TextEffect>,
+}
+
+/// Prints left-aligned columns, padding on the visible text so effects don't skew the layout
+pub fn print_table(headers: &[&str], rows: &[TableRow]) {
+    const COLUMN_PADDING: usize = 4;
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(&row.cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let pad = |column: usize, text: &str| {
+        if column + 1 == widths.len() {
+            String::new()
+        } else {
+            " ".repeat(widths[column] - text.chars().count() + COLUMN_PADDING)
+        }
+    };
+
+    let header_line: String = headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| format!("{header}{}", pad(column, header)))
+        .collect();
+    println!("{header_line}");
+    println!(
+        "{}",
+        "-".repeat(widths.iter().sum::<usize>() + COLUMN_PADDING * (widths.len() - 1))
+    );
+
+    for row in rows {
+        let line: String = row
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(column, cell)| {
+                let text = cell.trim_start();
+                let indent = &cell[..cell.len() - text.len()];
+                let text = match std::io::stdout().is_terminal() {
+                    true => hyperlink_urls(text),
+                    false => text.to_string(),
+                };
+                let styled = row
+                    .effects
+                    .iter()
+                    .fold(text, |text, effect| add_text_effect(&text, *effect));
+                format!("{indent}{styled}{}", pad(column, cell))
+            })
+            .collect();
+        println!("{line}");
+    }
+}
+
+pub fn is_url(reference: &str) -> bool {
+    reference.contains("://") || reference.starts_with("mailto:")
+}
+
+/// The URL a whitespace separated word holds, without trailing punctuation
+fn url_in(word: &str) -> Option<&str> {
+    let url = word.trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+    (url.starts_with("http://") || url.starts_with("https://")).then_some(url)
+}
+
+/// Every http(s) URL written in `text`
+pub fn find_urls(text: &str) -> Vec<&str> {
+    text.split_whitespace().filter_map(url_in).collect()
+}
+
+/// Turns URLs in `text` into OSC 8 hyperlinks, which terminals without support just ignore
+pub fn hyperlink_urls(text: &str) -> String {
+    text.split(' ')
+        .map(|word| match url_in(word) {
+            Some(url) => word.replacen(url, &format!("\x1b]8;;{url}\x1b\\{url}\x1b]8;;\x1b\\"), 1),
+            None => word.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Opens a URL or file with the system's default application
+pub fn open_external(target: &str) -> std::io::Result<bool> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        // Not `cmd /C start`, which would run whatever follows a `&` or `|` in the target
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    Ok(command.arg(target).status()?.success())
+}
+
+/// Asks a yes/no question on stdin, treating anything but an explicit yes as no
+pub fn confirm(prompt: &str) -> bool {
+    print!("{prompt} [y/N] ");
+    if std::io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Parses lengths of time like "45m", "1.5h", "2d" or "1w2d", where a day is 24 hours
+pub fn parse_duration(s: &str) -> Option<TimeDelta> {
+    let s = s.trim().to_lowercase();
+    let mut total = TimeDelta::zero();
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let minutes_per_unit = match c {
+            'm' => 1.0,
+            'h' => 60.0,
+            'd' => 60.0 * 24.0,
+            'w' => 60.0 * 24.0 * 7.0,
+            _ => return None,
+        };
+        let amount: f64 = number.parse().ok()?;
+        // Lengths too long for a TimeDelta saturate the cast, then fail here rather than panic
+        let seconds = TimeDelta::try_seconds((amount * minutes_per_unit * 60.0).round() as i64)?;
+        total = total.checked_add(&seconds)?;
+        number.clear();
+    }
+    match number.is_empty() && !s.is_empty() {
+        true => Some(total),
+        false => None,
+    }
+}
+
+/// Formats a length of time in hours and minutes, like "1h30m"
+pub fn format_duration(delta: TimeDelta) -> String {
+    let sign = if delta < TimeDelta::zero() { "-" } else { "" };
+    let minutes = delta.num_minutes().abs();
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{sign}{minutes}m"),
+        (hours, 0) => format!("{sign}{hours}h"),
+        (hours, minutes) => format!("{sign}{hours}h{minutes}m"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn find_and_hyperlink_urls() {
+        let name = "Review https://example.com/pr/12, then http://a.b (ask)";
+        assert_eq!(find_urls(name), ["https://example.com/pr/12", "http://a.b"]);
+        assert_eq!(
+            hyperlink_urls("See https://a.b."),
+            "See \x1b]8;;https://a.b\x1b\\https://a.b\x1b]8;;\x1b\\."
+        );
+        assert!(is_url("mailto:me@example.com"));
+        assert!(!is_url("/home/me/notes.txt"));
+    }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("45m"), Some(TimeDelta::minutes(45)));
+        assert_eq!(parse_duration("1.5h"), Some(TimeDelta::minutes(90)));
+        assert_eq!(parse_duration("1w2d"), Some(TimeDelta::days(9)));
+        assert_eq!(parse_duration("3"), None);
+        assert_eq!(parse_duration("3 hours"), None);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("99999999999w"), None);
+        assert_eq!(parse_duration("10000000000w10000000000w"), None);
+    }
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(format_duration(TimeDelta::minutes(45)), "45m");
+        assert_eq!(format_duration(TimeDelta::minutes(120)), "2h");
+        assert_eq!(format_duration(TimeDelta::minutes(-90)), "-1h30m");
+    }
+}